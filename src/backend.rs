@@ -1,25 +1,153 @@
-// TODO:
-//  - Improve the calculations for the character grid.
-//  - Explicit set font size, margins, etc (we can't rely on the user defining CSS for us)
-
-use base16_palettes::{Base16Accent, Base16Color, Base16Palette, Base16Shade, Palette, Shade};
+use base16_palettes::{Base16Accent, Base16Color, Base16Shade, Shade};
 use ratatui::{
-    buffer::Cell,
+    backend::ClearType,
+    buffer::{Buffer, Cell},
+    layout::{Position, Size},
     prelude::{Backend, Rect},
     style::{Color, Modifier, Style, Styled},
 };
-use std::{borrow::Cow, io::Result};
-use web_sys::{wasm_bindgen::JsValue, CssStyleSheet, MouseEvent};
-use yew::{html, Callback, Html};
+use std::{borrow::Cow, collections::VecDeque, io::Result};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+use web_sys::{
+    wasm_bindgen::{JsCast, JsValue},
+    Element, HtmlElement, HtmlStyleElement, MouseEvent,
+};
+use yew::{classes, html, Callback, Component, Context, Html, NodeRef, Properties};
+
+use crate::{
+    config::TermConfig,
+    embed::{find_regions, EmbedRegion},
+    glyphs::render_glyph,
+    palette::TermPalette,
+    selection::Selection,
+};
 
 /// The backend used to render text to HTML.
 /// The backend used to take ratatui widgets and render them into HTML.
+///
+/// This implements ratatui 0.26's `Backend` trait, which is what [`WebTerminal`](crate::WebTerminal)
+/// uses. The `ratatui_0_28` and `ratatui_0_29` features also implement the `Backend` trait of those
+/// releases. An app built on one of them can drive a `Terminal` from that release and place the
+/// result of [`YewBackend::view`] (or [`YewBackend::hydrate`]) in its own component's view.
 #[derive(Debug)]
 pub struct YewBackend {
     buffer: Vec<Vec<Cell>>,
     pre_hydrated: Vec<Vec<TermSpan>>,
-    rendered: Html,
-    palette: Palette,
+    /// The regions that were marked for embedding during the last render.
+    regions: Vec<EmbedRegion>,
+    palette: TermPalette,
+    /// The `<style>` element that the backend injects into the document and owns. All of the
+    /// backend's theme rules are written here rather than into the page's stylesheets.
+    theme: Option<HtmlStyleElement>,
+    style_mode: StyleMode,
+    custom_glyphs: CustomGlyphs,
+    config: TermConfig,
+    /// The multiplier applied to the configured font size.
+    font_scale: f64,
+    /// The width and height of a cell, in pixels.
+    cell_size: (f64, f64),
+    /// The location of the cursor, as set by the last frame.
+    cursor: (u16, u16),
+    /// Whether the cursor should be rendered.
+    cursor_visible: bool,
+    /// The container that the terminal is rendered into. This is used to map mouse positions to
+    /// cells.
+    container: NodeRef,
+    /// The cells that the user has selected.
+    selection: Option<Selection>,
+    /// The regions of the terminal that can't be selected, as given by the app.
+    unselectable: Vec<Rect>,
+    /// How the terminal is sized.
+    viewport: ViewportMode,
+    /// The multiplier that fits a fixed-size terminal to the window.
+    fit_scale: f64,
+    /// The lines that have scrolled off of the top of the terminal, oldest first.
+    history: VecDeque<Html>,
+    /// The history rendered as a single list. This is rebuilt only after the history changes,
+    /// rather than on every frame.
+    history_view: Option<Html>,
+    /// The number of lines that have ever been pushed into the history.
+    history_pushed: usize,
+    /// The maximum number of lines in the history, if scrollback is enabled.
+    scrollback: Option<usize>,
+}
+
+/// Determines how the backend attaches styles to the spans that it renders.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum StyleMode {
+    /// Every span carries its full style in an inline `style` attribute.
+    #[default]
+    Inline,
+    /// Spans reference CSS classes that are defined in the backend's `<style>` element. This
+    /// greatly reduces the size of the DOM, and palette changes only need to rewrite that
+    /// stylesheet. RGB colors can't be enumerated ahead of time, so they are still inlined.
+    Classes,
+}
+
+/// Determines how the size of the terminal's character grid is chosen.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ViewportMode {
+    /// The grid fills the window (or, on mobile, the screen).
+    #[default]
+    Fullscreen,
+    /// The grid has exactly the given number of columns and rows, regardless of the window. The
+    /// grid is centered in the window, both horizontally and vertically.
+    Fixed {
+        /// The width of the grid, in cells.
+        columns: u16,
+        /// The height of the grid, in cells.
+        rows: u16,
+        /// How the grid is fit to the window.
+        fit: FixedFit,
+    },
+    /// The grid fills the width of the window and is the given number of rows tall. Lines that
+    /// are inserted above it with `Terminal::insert_before` are appended to the page, which grows
+    /// like the history of a console. Unless scrollback is enabled, the history is unbounded.
+    Inline(u16),
+}
+
+/// Determines how a fixed-size terminal is fit to the window.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum FixedFit {
+    /// The terminal is rendered at the configured font size and centered in the window, both
+    /// horizontally and vertically.
+    #[default]
+    Center,
+    /// The font is scaled so that the terminal fills as much of the window as possible.
+    Scale,
+}
+
+/// Selects the groups of characters that the backend draws itself rather than leaving them to the
+/// font. Drawn characters are sized to exactly fill their cell, so they connect seamlessly
+/// regardless of the font and its line height.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CustomGlyphs {
+    /// Draw the box-drawing characters (e.g. `─`, `│`, and `┌`), which are used by widgets like
+    /// `Block` and `Table`.
+    pub box_drawing: bool,
+    /// Draw the block elements (e.g. `█`, `▄`, and `▏`), which are used by widgets like `Gauge` and
+    /// `BarChart` as well as by the `Canvas` widget's half-block marker.
+    pub block_elements: bool,
+    /// Draw the braille patterns (e.g. `⣿`), which are used by the `Canvas` and `Chart` widgets'
+    /// braille marker. Each raised dot is drawn as a small box within the cell.
+    pub braille: bool,
+}
+
+impl CustomGlyphs {
+    /// Draws every group of characters that the backend supports.
+    pub fn all() -> Self {
+        Self {
+            box_drawing: true,
+            block_elements: true,
+            braille: true,
+        }
+    }
+
+    /// Returns if the backend draws any characters itself.
+    fn any(self) -> bool {
+        self.box_drawing || self.block_elements || self.braille
+    }
 }
 
 /// The intermediate representation used for the hydration process.
@@ -98,126 +226,330 @@ pub const HYDRATION: Modifier = Modifier::SLOW_BLINK;
 impl YewBackend {
     /// The constructor for the terminal.
     pub fn new() -> Self {
-        let digest = Self {
-            buffer: Self::get_sized_buffer(),
+        Self::new_with_config(TermConfig::default())
+    }
+
+    /// The constructor for the terminal that uses the given font settings.
+    pub fn new_with_config(config: TermConfig) -> Self {
+        Self::new_with_settings(config, None)
+    }
+
+    /// The constructor for the terminal that uses the given font settings and gives the given
+    /// nonce to the backend's `<style>` element. See [`YewBackend::set_style_nonce`] for details.
+    pub fn new_with_style_nonce(config: TermConfig, nonce: &str) -> Self {
+        Self::new_with_settings(config, Some(nonce))
+    }
+
+    fn new_with_settings(config: TermConfig, nonce: Option<&str>) -> Self {
+        let config = config.sanitized();
+        let theme = create_theme_element(nonce);
+        let cell_size = measure_cell_size(&config);
+        Self::new_with_parts(config, theme, cell_size, ViewportMode::default())
+    }
+
+    /// Creates a backend that doesn't touch the DOM, which allows it to be used in unit tests.
+    #[cfg(test)]
+    pub(crate) fn headless(columns: u16, rows: u16) -> Self {
+        let config = TermConfig::default();
+        let cell_size = config.estimated_cell_size();
+        let fit = FixedFit::Center;
+        let viewport = ViewportMode::Fixed { columns, rows, fit };
+        Self::new_with_parts(config, None, cell_size, viewport)
+    }
+
+    fn new_with_parts(
+        config: TermConfig,
+        theme: Option<HtmlStyleElement>,
+        cell_size: (f64, f64),
+        viewport: ViewportMode,
+    ) -> Self {
+        let mut digest = Self {
+            buffer: Vec::new(),
             pre_hydrated: Vec::new(),
-            rendered: Html::default(),
-            palette: Palette::default(),
+            regions: Vec::new(),
+            palette: TermPalette::default(),
+            theme,
+            style_mode: StyleMode::default(),
+            custom_glyphs: CustomGlyphs::default(),
+            cell_size,
+            config,
+            font_scale: 1.0,
+            cursor: (0, 0),
+            cursor_visible: false,
+            container: NodeRef::default(),
+            selection: None,
+            unselectable: Vec::new(),
+            viewport,
+            fit_scale: 1.0,
+            history: VecDeque::new(),
+            history_view: None,
+            history_pushed: 0,
+            scrollback: None,
         };
-        digest.refresh_body_bg();
+        digest.buffer = digest.get_sized_buffer();
+        digest.refresh_theme();
         digest
     }
 
-    /// The constructor for the terminal.
-    pub fn new_with_palette(palette: Palette) -> Self {
+    /// The constructor for the terminal that uses the given palette. This can either be a
+    /// [`Palette`](base16_palettes::Palette) preset or a [`CustomPalette`](crate::palette::CustomPalette).
+    pub fn new_with_palette<P: Into<TermPalette>>(palette: P) -> Self {
         let mut digest = Self::new();
         digest.update_palette(palette);
         digest
     }
 
-    /// Sets the active style sheet's background color to the default terminal background color.
-    /// This helps the terminal area blend into the unrendered/non-terminal areas
-    pub(crate) fn refresh_body_bg(&self) {
-        let styles = web_sys::window()
-            .unwrap()
-            .document()
-            .unwrap()
-            .style_sheets();
-        let index = styles.length().saturating_sub(1);
-        let style = styles.get(index).unwrap();
-        let css = CssStyleSheet::from(JsValue::from(style));
-        let rules = css.css_rules().unwrap();
-        let index = (0..rules.length())
-            .filter_map(|i| rules.get(i).map(|r| (i, r)))
-            .find_map(|(i, r)| {
-                r.css_text()
-                    .starts_with("body { background-color: ")
-                    .then_some(i)
-            });
-        if let Some(i) = index {
-            css.delete_rule(i).unwrap();
+    /// Rewrites the backend's theme rules. This sets the body's background color to the default
+    /// terminal background color, which helps the terminal area blend into the
+    /// unrendered/non-terminal areas. This also (re)defines the classes used by
+    /// [`StyleMode::Classes`] and the classes that lay out the terminal.
+    pub(crate) fn refresh_theme(&self) {
+        if let Some(theme) = self.theme.as_ref() {
+            let mut css = theme_css(&self.palette);
+            let config = self.config.scaled(self.scale());
+            css.push_str(&layout_css(&config, self.grid_offset()));
+            theme.set_text_content(Some(&css));
         }
-        let text = format!(
-            "body {{ background-color: {}; }}",
-            self.palette.to_hex_str(Base16Color::default_bg())
-        );
-        css.insert_rule(&text).unwrap();
+    }
+
+    /// Sets the nonce of the backend's `<style>` element so that its rules are allowed by a
+    /// Content Security Policy that uses `style-src 'nonce-...'`. The element is replaced by one
+    /// that carries the nonce. The original element was inserted without a nonce, so prefer
+    /// [`YewBackend::new_with_style_nonce`], which avoids the resulting CSP violation.
+    ///
+    /// The nonce covers the terminal's layout and, with [`StyleMode::Classes`], its text styles.
+    /// Elements that are positioned over individual cells (the cursor, the selection highlight,
+    /// embedded regions, wide characters, and custom glyphs) and [`StyleMode::Inline`] still use
+    /// `style` attributes, which such a policy only allows with `'unsafe-inline'`.
+    pub fn set_style_nonce(&mut self, nonce: &str) {
+        if let Some(theme) = self.theme.take() {
+            theme.remove();
+        }
+        self.theme = create_theme_element(Some(nonce));
+        self.refresh_theme();
+    }
+
+    /// Sets how the backend attaches styles to the spans that it renders.
+    pub fn set_style_mode(&mut self, mode: StyleMode) {
+        self.style_mode = mode;
+    }
+
+    /// Sets which groups of characters the backend draws itself rather than leaving them to the
+    /// font.
+    pub fn set_custom_glyphs(&mut self, glyphs: CustomGlyphs) {
+        self.custom_glyphs = glyphs;
     }
 
     /// Updates the palette used to render indexed colors.
-    pub fn update_palette(&mut self, palette: Palette) {
-        self.palette = palette;
-        self.refresh_body_bg();
+    pub fn update_palette<P: Into<TermPalette>>(&mut self, palette: P) {
+        self.palette = palette.into();
+        self.refresh_theme();
     }
 
-    fn get_sized_buffer() -> Vec<Vec<Cell>> {
-        let (width, height) = if is_mobile() {
-            get_screen_size()
-        } else {
-            get_window_size()
+    /// The bounds for the font scale.
+    const FONT_SCALE_RANGE: (f64, f64) = (0.25, 5.0);
+
+    /// Updates the font settings and recalculates the size of the character grid.
+    pub fn update_config(&mut self, config: TermConfig) {
+        self.config = config.sanitized();
+        self.resize_buffer();
+    }
+
+    /// Sets the multiplier that is applied to the configured font size (e.g. `1.5` renders text
+    /// 50% larger) and recalculates the size of the character grid. The scale is clamped between
+    /// `0.25` and `5.0`, and a scale that is NaN is ignored. Returns if the size of the grid
+    /// changed.
+    pub fn set_font_scale(&mut self, scale: f64) -> bool {
+        if scale.is_nan() {
+            return false;
+        }
+        let (min, max) = Self::FONT_SCALE_RANGE;
+        self.font_scale = scale.clamp(min, max);
+        self.resize_buffer()
+    }
+
+    /// Returns the multiplier that is applied to the configured font size.
+    pub fn font_scale(&self) -> f64 {
+        self.font_scale
+    }
+
+    /// Returns the current font settings.
+    pub fn config(&self) -> &TermConfig {
+        &self.config
+    }
+
+    /// Sets how the terminal is sized and recalculates the size of the character grid. Returns if
+    /// the size of the grid changed.
+    pub fn set_viewport(&mut self, viewport: ViewportMode) -> bool {
+        self.viewport = viewport;
+        self.resize_buffer()
+    }
+
+    /// Returns how the terminal is sized.
+    pub fn viewport(&self) -> ViewportMode {
+        self.viewport
+    }
+
+    /// Returns the distance, in pixels, between the top of the page and the grid. A fixed-size
+    /// grid is centered vertically in the window, while other grids start at the top.
+    fn grid_offset(&self) -> f64 {
+        let ViewportMode::Fixed { rows, .. } = self.viewport else {
+            return 0.0;
         };
+        let (_, h) = available_size();
+        ((h - rows as f64 * self.cell_size.1) / 2.0).max(0.0)
+    }
+
+    /// Returns the multiplier that is applied to the configured font size, including the scaling
+    /// that fits a fixed-size terminal to the window.
+    fn scale(&self) -> f64 {
+        self.font_scale * self.fit_scale
+    }
+
+    /// Calculates the size of the character grid. Unless the viewport is fixed, this is the number
+    /// of characters that can fit in the window (or, on mobile, the screen).
+    fn grid_size(&self) -> (u16, u16) {
+        let (cell_w, cell_h) = self.cell_size;
+        match self.viewport {
+            ViewportMode::Fullscreen => {
+                let (w, h) = available_size();
+                ((w / cell_w) as u16, (h / cell_h) as u16)
+            }
+            ViewportMode::Fixed { columns, rows, .. } => (columns, rows),
+            // The line above the viewport holds the line that was most recently inserted
+            ViewportMode::Inline(height) => {
+                let (w, _) = available_size();
+                ((w / cell_w) as u16, height.saturating_add(1))
+            }
+        }
+    }
+
+    fn get_sized_buffer(&self) -> Vec<Vec<Cell>> {
+        let (width, height) = self.grid_size();
         vec![vec![Cell::default(); width as usize]; height as usize]
     }
 
-    /// The method that renders the temrinal data into HTML.
+    /// The method that renders the temrinal data into HTML. Spans that need hydration and embedded
+    /// regions are rendered as plain text. See [`YewBackend::hydrate`] to fill them in.
     pub fn view(&mut self) -> Html {
-        self.rendered.clone()
+        self.hydrate(|_| {}, |_| {})
     }
 
     /// The rendering process is split into three steps.
     fn prerender(&mut self) {
-        let Some(cell) = self.buffer.first().and_then(|l| l.first()) else {
+        self.regions = find_regions(&self.buffer);
+        self.pre_hydrated = self.buffer.iter().map(|line| line_spans(line)).collect();
+    }
+
+    /// Returns the renderer for the backend's current settings.
+    fn renderer(&self) -> SpanRenderer<'_> {
+        SpanRenderer {
+            palette: &self.palette,
+            mode: self.style_mode,
+            glyphs: self.custom_glyphs,
+            cell_size: self.cell_size,
+        }
+    }
+
+    /// Sets the maximum number of lines that are kept in the history. Setting a maximum enables
+    /// scrollback: the history is kept regardless of the viewport, and it is scrolled by the page
+    /// rather than by the app. Passing `None` disables scrollback, in which case only an inline
+    /// terminal keeps a history (with no maximum).
+    pub fn set_scrollback(&mut self, max_lines: Option<usize>) {
+        self.scrollback = max_lines;
+        self.trim_history();
+    }
+
+    /// Returns the maximum number of lines that are kept in the history, if scrollback is enabled.
+    pub fn scrollback(&self) -> Option<usize> {
+        self.scrollback
+    }
+
+    /// Returns if lines that leave the terminal are kept in the history.
+    fn keeps_history(&self) -> bool {
+        self.scrollback.is_some() || matches!(self.viewport, ViewportMode::Inline(_))
+    }
+
+    /// Appends the lines of the given buffer to the history, as if they had scrolled off of the
+    /// top of the terminal. Does nothing if the history isn't kept.
+    pub fn push_history(&mut self, lines: &Buffer) {
+        if !self.keeps_history() || lines.area.width == 0 {
             return;
-        };
+        }
+        for line in lines.content.chunks(lines.area.width as usize) {
+            self.push_history_line(line);
+        }
+        self.trim_history();
+    }
 
-        let mut fg = cell.fg;
-        let mut bg = cell.bg;
-        let mut mods = cell.modifier;
-        for line in self.buffer.iter() {
-            let mut text = String::with_capacity(line.len());
-            let mut line_buf: Vec<TermSpan> = Vec::new();
-            for c in line {
-                if fg != c.fg || bg != c.bg || mods != c.modifier {
-                    // Create a new node, clear the text buffer, update the foreground/background
-                    if !text.is_empty() {
-                        let span = if mods.contains(HYDRATION) {
-                            TermSpan::Dehydrated(DehydratedSpan::new(fg, bg, mods, text.to_owned()))
-                        } else {
-                            TermSpan::Plain((fg, bg), mods, text.to_owned())
-                        };
-                        line_buf.push(span);
-                    }
-                    mods = c.modifier;
-                    fg = c.fg;
-                    bg = c.bg;
-                    text.clear();
-                }
-                text.push_str(c.symbol())
+    /// Renders a line that has scrolled off of the top of the buffer into the history. History
+    /// lines are static, so they are never hydrated. Each line is keyed by the number of lines that
+    /// were pushed before it, so dropping the oldest lines doesn't cause the rest to be patched.
+    fn push_history_line(&mut self, line: &[Cell]) {
+        let renderer = self.renderer();
+        let inner = line_spans(line).into_iter().map(|span| match span {
+            TermSpan::Plain((fg, bg), mods, text) => renderer.span(fg, bg, mods, &text),
+            TermSpan::Dehydrated(span) => {
+                let (fg, bg) = span.style;
+                renderer.span(fg, bg, span.mods, &span.text)
             }
-            // Create a new node, combine into a `pre` tag, push onto buf
-            if !text.is_empty() {
-                let span = if mods.contains(HYDRATION) {
-                    TermSpan::Dehydrated(DehydratedSpan::new(fg, bg, mods, text.to_owned()))
-                } else {
-                    TermSpan::Plain((fg, bg), mods, text.to_owned())
-                };
-                line_buf.push(span);
+        });
+        let key = self.history_pushed;
+        let line = html! { <pre { key } class={ LINE_CLASS }> { for inner } </pre> };
+        self.history.push_back(line);
+        self.history_view = None;
+        self.history_pushed += 1;
+    }
+
+    /// Drops the oldest lines of the history until it fits in the scrollback.
+    fn trim_history(&mut self) {
+        if let Some(max) = self.scrollback {
+            let excess = self.history.len().saturating_sub(max);
+            if excess > 0 {
+                self.history.drain(..excess);
+                self.history_view = None;
             }
-            self.pre_hydrated.push(line_buf);
         }
     }
 
-    pub(crate) fn hydrate<F>(&mut self, mut hydrator: F) -> Html
+    /// Returns the number of lines that have ever been pushed into the history. Unlike the length
+    /// of the history, this keeps growing once the scrollback is full.
+    pub(crate) fn history_pushed(&self) -> usize {
+        self.history_pushed
+    }
+
+    /// Renders the last frame into HTML. Each span that needs hydration is passed to the hydrator
+    /// and each embedded region is passed to the embedder before they are rendered. Call this after
+    /// each draw, as the frame's spans and regions are consumed.
+    pub fn hydrate<F, E>(&mut self, hydrator: F, embedder: E) -> Html
     where
         F: FnMut(&mut DehydratedSpan),
+        E: FnMut(&mut EmbedRegion),
     {
-        let mut buffer: Vec<Html> = Vec::with_capacity(self.pre_hydrated.len());
-        for line in self.pre_hydrated.drain(0..) {
+        self.hydrate_with_input(hydrator, embedder, None)
+    }
+
+    /// Renders the last frame like [`YewBackend::hydrate`], placing the given text input under the
+    /// cursor.
+    pub(crate) fn hydrate_with_input<F, E>(
+        &mut self,
+        mut hydrator: F,
+        mut embedder: E,
+        input: Option<Html>,
+    ) -> Html
+    where
+        F: FnMut(&mut DehydratedSpan),
+        E: FnMut(&mut EmbedRegion),
+    {
+        let pre_hydrated = std::mem::take(&mut self.pre_hydrated);
+        let renderer = self.renderer();
+        let mut buffer: Vec<Html> = Vec::with_capacity(pre_hydrated.len());
+        for line in pre_hydrated {
             let mut inner: Vec<Html> = Vec::with_capacity(line.len());
             for span in line {
                 match span {
                     TermSpan::Plain((fg, bg), mods, text) => {
-                        inner.push(create_span(&self.palette, fg, bg, mods, &text))
+                        inner.push(renderer.span(fg, bg, mods, &text))
                     }
                     TermSpan::Dehydrated(mut span) => {
                         hydrator(&mut span);
@@ -232,28 +564,166 @@ impl YewBackend {
                             hyperlink,
                         } = interaction;
                         let mut element =
-                            create_span_with_callback(&self.palette, fg, bg, mods, &text, on_click);
+                            renderer.span_with_callback(fg, bg, mods, &text, on_click);
                         if let Some(link) = hyperlink {
-                            element = html! { <a href = { link } target = "_blank" style="text-decoration:none"> { element } </a> };
+                            element = html! { <a href = { link } target = "_blank" class={ LINK_CLASS }> { element } </a> };
                         }
                         inner.push(element);
                     }
                 }
             }
-            buffer.push(html! { <pre style="margin: 0px"> { for inner.drain(0..) } </pre> })
+            buffer.push(html! { <pre class={ LINE_CLASS }> { for inner.drain(0..) } </pre> })
         }
-        html! { <div style="width: fit-content; block-size: fit-content; margin: auto;"> { for buffer.into_iter() } </div> }
-    }
-
-    pub(crate) fn resize_buffer(&mut self) {
-        let (width, height) = if is_mobile() {
-            get_screen_size()
+        let lines: Html = buffer.into_iter().collect();
+        let overlays: Html = self
+            .regions
+            .drain(0..)
+            .filter_map(|mut region| {
+                embedder(&mut region);
+                region.render(self.cell_size)
+            })
+            .collect();
+        let selection: Html = self
+            .selection
+            .filter(|s| !s.is_empty())
+            .map(|s| s.render(self.buffer_size(), &self.unselectable, self.cell_size))
+            .unwrap_or_default()
+            .into_iter()
+            .collect();
+        let cursor = if self.cursor_visible || input.is_some() {
+            self.render_cursor(input)
         } else {
-            get_window_size()
+            Html::default()
         };
-        if self.buffer.len() != height as usize || self.buffer[0].len() != width as usize {
+        // Each group of nodes is kept in its own list. Otherwise, a change in the number of
+        // selection rows or in the cursor's visibility would shift the embedded content onto other
+        // nodes, which re-creates it (e.g. an `<input>` loses its focus and text).
+        let grid = html! { <div ref={ self.container.clone() } class={ classes!(TERM_CLASS, GRID_CLASS) }> { lines } { overlays } { selection } { cursor } </div> };
+        if self.history.is_empty() {
+            return grid;
+        }
+        let lines = self
+            .history_view
+            .get_or_insert_with(|| self.history.iter().cloned().collect())
+            .clone();
+        let (pushed, len) = (self.history_pushed, self.history.len());
+        html! {
+            <>
+                <div class={ TERM_CLASS }> <History { lines } { pushed } { len } /> </div>
+                { grid }
+            </>
+        }
+    }
+
+    /// Renders the cursor as a block over its cell. The text input, if there is one, is placed
+    /// under the cursor so that the browser positions IME popups next to it.
+    fn render_cursor(&self, input: Option<Html>) -> Html {
+        let (x, y) = self.cursor;
+        let (cell_w, cell_h) = self.cell_size;
+        let style = format!(
+            "position: absolute; left: {}px; top: {}px; width: {cell_w}px; height: {cell_h}px;",
+            x as f64 * cell_w,
+            y as f64 * cell_h,
+        );
+        let block = self.cursor_visible.then(|| {
+            html! {
+                <div class={ CURSOR_CLASS } />
+            }
+        });
+        html! { <div { style }> { for input } { for block } </div> }
+    }
+
+    /// Returns the width and height of the buffer, in cells.
+    fn buffer_size(&self) -> (u16, u16) {
+        let width = self.buffer.first().map(Vec::len).unwrap_or_default();
+        (width as u16, self.buffer.len() as u16)
+    }
+
+    /// Returns the cell that lies under the given client coordinates (e.g. of a mouse event).
+    /// Positions outside of the terminal are clamped to its nearest edge.
+    pub(crate) fn cell_at(&self, x: i32, y: i32) -> Option<(u16, u16)> {
+        let rect = self.container.cast::<Element>()?.get_bounding_client_rect();
+        let (width, height) = self.buffer_size();
+        let (cell_w, cell_h) = self.cell_size;
+        let to_cell = |pos: f64, cell: f64, max: u16| (pos / cell).max(0.0).min(max as f64) as u16;
+        Some((
+            to_cell(x as f64 - rect.left(), cell_w, width.checked_sub(1)?),
+            to_cell(y as f64 - rect.top(), cell_h, height.checked_sub(1)?),
+        ))
+    }
+
+    /// Returns the location of the cursor. This mirrors the `Position`-based cursor API of newer
+    /// ratatui releases, which replaces `Backend::get_cursor`.
+    pub fn get_cursor_position(&self) -> Position {
+        self.cursor.into()
+    }
+
+    /// Moves the cursor to the given location. This mirrors the `Position`-based cursor API of
+    /// newer ratatui releases, which replaces `Backend::set_cursor`.
+    pub fn set_cursor_position<P: Into<Position>>(&mut self, position: P) {
+        self.cursor = position.into().into();
+    }
+
+    /// Returns the user's current selection.
+    pub fn selection(&self) -> Option<&Selection> {
+        self.selection.as_ref()
+    }
+
+    /// Replaces the user's selection. Passing `None` removes the selection.
+    pub fn set_selection(&mut self, selection: Option<Selection>) {
+        self.selection = selection;
+    }
+
+    /// Returns the text of the selected cells, or `None` if nothing is selected. The text is
+    /// taken from the last frame, so borders and other decorations are copied as they appear
+    /// unless they lie in an unselectable region.
+    pub fn selected_text(&self) -> Option<String> {
+        self.selection
+            .filter(|s| !s.is_empty())
+            .map(|s| s.text(&self.buffer, &self.unselectable))
+    }
+
+    /// Sets the regions that are excluded from selections.
+    pub(crate) fn set_unselectable(&mut self, regions: Vec<Rect>) {
+        self.unselectable = regions;
+    }
+
+    /// Recalculates the size of the character grid and returns if the size changed.
+    pub(crate) fn resize_buffer(&mut self) -> bool {
+        // Web fonts might have loaded since the last measurement, so the cell is re-measured
+        self.fit_scale = 1.0;
+        self.cell_size = measure_cell_size(&self.config.scaled(self.font_scale));
+        if let ViewportMode::Fixed {
+            columns,
+            rows,
+            fit: FixedFit::Scale,
+        } = self.viewport
+        {
+            let (w, h) = available_size();
+            let (cell_w, cell_h) = self.cell_size;
+            let fit = (w / (columns as f64 * cell_w)).min(h / (rows as f64 * cell_h));
+            if fit.is_finite() && fit > 0.0 {
+                self.fit_scale = fit;
+                self.cell_size = measure_cell_size(&self.config.scaled(self.scale()));
+            }
+        }
+        let (width, height) = self.grid_size();
+        let resized = self.buffer.len() != height as usize
+            || self.buffer.first().map(Vec::len) != Some(width as usize);
+        if resized {
             // Reset the buffer only if the size is actually different
-            self.buffer = Self::get_sized_buffer();
+            self.buffer = self.get_sized_buffer();
+            self.selection = None;
+        }
+        self.refresh_theme();
+        resized
+    }
+}
+
+impl Drop for YewBackend {
+    fn drop(&mut self) {
+        if let Some(theme) = self.theme.take() {
+            theme.remove();
         }
     }
 }
@@ -273,38 +743,77 @@ impl Backend for YewBackend {
         Ok(())
     }
 
+    /// Moves the cursor down by `n` lines, like printing `n` line breaks. Once the cursor reaches
+    /// the last line, the buffer's top line is scrolled off to make room for each new line.
+    fn append_lines(&mut self, n: u16) -> Result<()> {
+        let (width, height) = self.buffer_size();
+        for _ in 0..n {
+            if self.cursor.1 + 1 < height {
+                self.cursor.1 += 1;
+            } else if height > 0 {
+                let line = self.buffer.remove(0);
+                if self.keeps_history() {
+                    self.push_history_line(&line);
+                    self.trim_history();
+                }
+                self.buffer.push(vec![Cell::default(); width as usize]);
+            }
+        }
+        Ok(())
+    }
+
     fn hide_cursor(&mut self) -> Result<()> {
+        self.cursor_visible = false;
         Ok(())
     }
 
     fn show_cursor(&mut self) -> Result<()> {
-        todo!()
+        self.cursor_visible = true;
+        Ok(())
     }
 
     fn get_cursor(&mut self) -> Result<(u16, u16)> {
-        todo!()
+        Ok(self.cursor)
     }
 
-    fn set_cursor(&mut self, _x: u16, _y: u16) -> Result<()> {
-        todo!()
+    fn set_cursor(&mut self, x: u16, y: u16) -> Result<()> {
+        self.cursor = (x, y);
+        Ok(())
     }
 
     fn clear(&mut self) -> Result<()> {
-        self.buffer = Self::get_sized_buffer();
+        self.clear_region(ClearType::All)
+    }
+
+    /// Clears part of the buffer in place. The buffer is not resized, as that only happens when
+    /// the window is resized.
+    fn clear_region(&mut self, clear_type: ClearType) -> Result<()> {
+        clear_cells(&mut self.buffer, self.cursor, clear_type);
         Ok(())
     }
 
     fn size(&self) -> Result<Rect> {
-        Ok(Rect::new(
-            0,
-            0,
-            self.buffer.first().unwrap().len().saturating_sub(1) as u16,
-            self.buffer.len().saturating_sub(1) as u16,
-        ))
+        let (width, height) = self.buffer_size();
+        // The edges of a grid that is sized to the window are left as slack
+        Ok(match self.viewport {
+            ViewportMode::Fullscreen => {
+                Rect::new(0, 0, width.saturating_sub(1), height.saturating_sub(1))
+            }
+            ViewportMode::Fixed { .. } => Rect::new(0, 0, width, height),
+            ViewportMode::Inline(_) => Rect::new(0, 0, width.saturating_sub(1), height),
+        })
     }
 
     fn window_size(&mut self) -> Result<ratatui::backend::WindowSize> {
-        todo!()
+        let (width, height) = self.buffer_size();
+        let (cell_w, cell_h) = self.cell_size;
+        Ok(ratatui::backend::WindowSize {
+            columns_rows: Size::new(width, height),
+            pixels: Size::new(
+                (width as f64 * cell_w) as u16,
+                (height as f64 * cell_h) as u16,
+            ),
+        })
     }
 
     fn flush(&mut self) -> Result<()> {
@@ -313,50 +822,495 @@ impl Backend for YewBackend {
     }
 }
 
-fn create_span(p: &Palette, fg: Color, bg: Color, mods: Modifier, text: &str) -> Html {
-    create_span_with_callback(p, fg, bg, mods, text, None)
+/// The properties of [`History`].
+#[derive(Properties)]
+struct HistoryProps {
+    /// The rendered lines of the history.
+    lines: Html,
+    /// The number of lines that have ever been pushed into the history.
+    pushed: usize,
+    /// The number of lines in the history.
+    len: usize,
 }
 
-fn create_span_with_callback(
-    p: &Palette,
-    fg: Color,
-    bg: Color,
-    mods: Modifier,
-    text: &str,
-    cb: Option<Callback<MouseEvent>>,
-) -> Html {
-    let fg = to_css_color(p, fg).unwrap_or_else(|| p.to_hex_str(Base16Color::default_fg()).into());
-    let bg = to_css_color(p, bg).unwrap_or_else(|| p.to_hex_str(Base16Color::default_bg()).into());
-    let mut style = format!("color: {fg}; background-color: {bg};");
-    extend_css(mods, &mut style);
-    match cb {
-        Some(cb) => html! { <span style={ style } onclick = { cb }> { text } </span> },
-        None => html! { <span style={ style }> { text } </span> },
+/// Only the counts are compared, as the lines only change when lines are pushed into or dropped
+/// from the history. Comparing the lines themselves would walk the whole history on every frame.
+impl PartialEq for HistoryProps {
+    fn eq(&self, other: &Self) -> bool {
+        self.pushed == other.pushed && self.len == other.len
+    }
+}
+
+/// Renders the terminal's history. This is its own component so that the history, which can be
+/// thousands of lines long, is only diffed when it changes rather than on every frame.
+struct History;
+
+impl Component for History {
+    type Message = ();
+    type Properties = HistoryProps;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        ctx.props().lines.clone()
+    }
+}
+
+/// Resets the cells of the grid that are covered by the given type of clear. Like a terminal's
+/// erase commands, `AfterCursor` and `UntilNewLine` include the cursor's cell, as does
+/// `BeforeCursor`. The cursor may lie outside of the grid, in which case only the cells that exist
+/// are cleared.
+fn clear_cells(grid: &mut [Vec<Cell>], (x, y): (u16, u16), clear_type: ClearType) {
+    let (x, y) = (x as usize, y as usize);
+    let reset = |cells: &mut [Cell]| cells.iter_mut().for_each(Cell::reset);
+    let height = grid.len();
+    match clear_type {
+        ClearType::All => grid.iter_mut().for_each(|line| reset(line)),
+        ClearType::AfterCursor => {
+            if let Some(line) = grid.get_mut(y) {
+                let x = x.min(line.len());
+                reset(&mut line[x..]);
+            }
+            for line in grid.iter_mut().skip(y + 1) {
+                reset(line);
+            }
+        }
+        ClearType::BeforeCursor => {
+            for line in grid.iter_mut().take(y.min(height)) {
+                reset(line);
+            }
+            if let Some(line) = grid.get_mut(y) {
+                let x = (x + 1).min(line.len());
+                reset(&mut line[..x]);
+            }
+        }
+        ClearType::CurrentLine => {
+            if let Some(line) = grid.get_mut(y) {
+                reset(line);
+            }
+        }
+        ClearType::UntilNewLine => {
+            if let Some(line) = grid.get_mut(y) {
+                let x = x.min(line.len());
+                reset(&mut line[x..]);
+            }
+        }
+    }
+}
+
+/// Creates the `<style>` element that holds the backend's theme rules and inserts it into the
+/// document. The CSP is checked when the element is inserted, so the nonce is set beforehand.
+fn create_theme_element(nonce: Option<&str>) -> Option<HtmlStyleElement> {
+    let theme: HtmlStyleElement = web_sys::window()?
+        .document()?
+        .create_element("style")
+        .ok()?
+        .dyn_into()
+        .ok()?;
+    theme.set_attribute("data-webatui", "theme").ok()?;
+    if let Some(nonce) = nonce {
+        theme.set_nonce(nonce);
+    }
+    insert_theme_element(&theme);
+    Some(theme)
+}
+
+/// Inserts the theme element into the document's head. If the page has no head, the body or, as a
+/// last resort, the root element is used instead.
+fn insert_theme_element(theme: &HtmlStyleElement) {
+    let Some(document) = web_sys::window().and_then(|w| w.document()) else {
+        return;
+    };
+    let parent = document
+        .head()
+        .map(JsCast::unchecked_into)
+        .or_else(|| document.body().map(JsCast::unchecked_into))
+        .or_else(|| document.document_element());
+    if let Some(parent) = parent {
+        let _ = parent.append_child(theme);
+    }
+}
+
+/// The prefix used by all of the CSS classes that the backend generates.
+const CLASS_PREFIX: &str = "webatui";
+
+/// The class of the container that the terminal's lines are rendered into.
+const TERM_CLASS: &str = "webatui-term";
+/// The class of the container of the terminal's live grid, as opposed to its history.
+const GRID_CLASS: &str = "webatui-grid";
+/// The class of each line of the terminal.
+const LINE_CLASS: &str = "webatui-line";
+/// The class of the hyperlinks that hydrated spans are wrapped in.
+const LINK_CLASS: &str = "webatui-link";
+/// The class of the block that is drawn over the cursor's cell.
+const CURSOR_CLASS: &str = "webatui-cursor";
+/// The class of the hidden `<textarea>` that receives text input.
+pub(crate) const INPUT_CLASS: &str = "webatui-input";
+/// The class that stops the browser from selecting the terminal's HTML.
+pub(crate) const NO_SELECT_CLASS: &str = "webatui-no-select";
+/// The class of the images that are placed over embedded regions.
+pub(crate) const IMAGE_CLASS: &str = "webatui-image";
+/// The class of the containers that hold the content placed over embedded regions.
+pub(crate) const EMBED_CLASS: &str = "webatui-embed";
+
+/// The named ratatui colors along with their CSS class suffix and their CSS color.
+const NAMED_COLORS: [(Color, &str, &str); 16] = [
+    (Color::Black, "black", "black"),
+    (Color::Red, "red", "red"),
+    (Color::Green, "green", "green"),
+    (Color::Yellow, "yellow", "yellow"),
+    (Color::Blue, "blue", "blue"),
+    (Color::Magenta, "magenta", "magenta"),
+    (Color::Cyan, "cyan", "cyan"),
+    (Color::Gray, "gray", "gray"),
+    (Color::DarkGray, "dark-gray", "darkgray"),
+    (Color::LightRed, "light-red", "#de2b56"),
+    (Color::LightGreen, "light-green", "lightgreen"),
+    (Color::LightYellow, "light-yellow", "LightGoldenRodYellow"),
+    (Color::LightBlue, "light-blue", "LightSkyBlue"),
+    (Color::LightMagenta, "light-magenta", "#ff00ff"),
+    (Color::LightCyan, "light-cyan", "lightcyan"),
+    (Color::White, "white", "white"),
+];
+
+/// The supported modifiers along with their CSS class suffix and their CSS declaration.
+const MODIFIER_CSS: [(Modifier, &str, &str); 3] = [
+    (Modifier::BOLD, "bold", "font-weight: bolder;"),
+    (Modifier::ITALIC, "italic", "font-style: oblique;"),
+    (
+        Modifier::UNDERLINED,
+        "underlined",
+        "text-decoration: underline;",
+    ),
+];
+
+/// The state needed to render spans of text into HTML.
+struct SpanRenderer<'a> {
+    palette: &'a TermPalette,
+    mode: StyleMode,
+    glyphs: CustomGlyphs,
+    /// The width and height of a cell, in pixels.
+    cell_size: (f64, f64),
+}
+
+impl SpanRenderer<'_> {
+    fn span(&self, fg: Color, bg: Color, mods: Modifier, text: &str) -> Html {
+        self.span_with_callback(fg, bg, mods, text, None)
+    }
+
+    fn span_with_callback(
+        &self,
+        fg: Color,
+        bg: Color,
+        mods: Modifier,
+        text: &str,
+        cb: Option<Callback<MouseEvent>>,
+    ) -> Html {
+        let p = self.palette;
+        let (class, style) = match self.mode {
+            StyleMode::Inline => {
+                let fg =
+                    to_css_color(p, fg).unwrap_or_else(|| p.to_hex_str(Base16Color::default_fg()));
+                let bg =
+                    to_css_color(p, bg).unwrap_or_else(|| p.to_hex_str(Base16Color::default_bg()));
+                let mut style = format!("color: {fg}; background-color: {bg};");
+                extend_css(mods, &mut style);
+                (None, Some(style))
+            }
+            StyleMode::Classes => to_css_classes(fg, bg, mods),
+        };
+        let text = self.text(text);
+        match cb {
+            Some(cb) => html! { <span { class } { style } onclick = { cb }> { text } </span> },
+            None => html! { <span { class } { style }> { text } </span> },
+        }
+    }
+
+    /// Renders the text of a span. Fonts rarely render wide characters (e.g. CJK and emoji) at
+    /// exactly twice the width of other characters, so each wide character is placed in a box
+    /// that is exactly as wide as the cells it covers. Characters that the backend draws itself
+    /// are also split out of the text.
+    fn text(&self, text: &str) -> Html {
+        if text.is_ascii() {
+            return html! { { text } };
+        }
+        let mut nodes: Vec<Html> = Vec::new();
+        let mut narrow = String::new();
+        for grapheme in text.graphemes(true) {
+            let width = grapheme.width();
+            let glyph = self
+                .glyphs
+                .any()
+                .then(|| single_char(grapheme))
+                .flatten()
+                .and_then(|c| render_glyph(c, self.glyphs, self.cell_size));
+            if width <= 1 && glyph.is_none() {
+                narrow.push_str(grapheme);
+                continue;
+            }
+            if !narrow.is_empty() {
+                nodes.push(html! { { std::mem::take(&mut narrow) } });
+            }
+            let node = glyph.unwrap_or_else(|| {
+                let style = format!(
+                    "display: inline-block; width: {}px; text-align: center;",
+                    self.cell_size.0 * width as f64
+                );
+                html! { <span { style }>{ grapheme.to_owned() }</span> }
+            });
+            nodes.push(node);
+        }
+        if !narrow.is_empty() {
+            nodes.push(html! { { narrow } });
+        }
+        html! { for nodes }
     }
 }
 
-fn to_css_color(p: &Palette, c: Color) -> Option<Cow<'static, str>> {
+/// Returns the grapheme's character if it consists of exactly one character.
+fn single_char(grapheme: &str) -> Option<char> {
+    let mut chars = grapheme.chars();
+    chars.next().filter(|_| chars.next().is_none())
+}
+
+/// Splits a span's style into the classes that it references and the inline style needed for
+/// anything that can't be expressed as a class (i.e. RGB colors).
+fn to_css_classes(fg: Color, bg: Color, mods: Modifier) -> (Option<String>, Option<String>) {
+    let mut classes = Vec::with_capacity(2 + MODIFIER_CSS.len());
+    let mut style = String::new();
+    for (kind, prop, color) in [("fg", "color", fg), ("bg", "background-color", bg)] {
+        match color {
+            Color::Rgb(r, g, b) => style.push_str(&format!("{prop}: {}; ", rgb_css(r, g, b))),
+            // Only the base16 colors have classes
+            Color::Indexed(i) if i >= 16 => {
+                let (r, g, b) = extended_rgb(i);
+                style.push_str(&format!("{prop}: {}; ", rgb_css(r, g, b)));
+            }
+            c => classes.push(format!("{CLASS_PREFIX}-{kind}-{}", to_css_class_suffix(c))),
+        }
+    }
+    classes.extend(
+        MODIFIER_CSS
+            .iter()
+            .filter(|(m, _, _)| mods.contains(*m))
+            .map(|(_, suffix, _)| format!("{CLASS_PREFIX}-{suffix}")),
+    );
+    let style = (!style.is_empty()).then_some(style);
+    (Some(classes.join(" ")), style)
+}
+
+/// Returns the suffix of the CSS class for the given (non-RGB) color.
+fn to_css_class_suffix(c: Color) -> Cow<'static, str> {
+    match c {
+        Color::Indexed(i) => i.to_string().into(),
+        c => named_color(c).map_or("reset", |(suffix, _)| suffix).into(),
+    }
+}
+
+/// Generates all of the backend's theme rules for the given palette.
+fn theme_css(p: &TermPalette) -> String {
+    let default_fg = p.to_hex_str(Base16Color::default_fg());
+    let default_bg = p.to_hex_str(Base16Color::default_bg());
+    let mut css = format!("body {{ background-color: {default_bg}; }}\n");
+    // Embedded content that doesn't fill its region (e.g. a letterboxed image) hides the region's
+    // text behind it
+    css.push_str(&format!(
+        ".{EMBED_CLASS} {{ background-color: {default_bg}; }}\n"
+    ));
+    css.push_str(&format!(
+        ".{CLASS_PREFIX}-fg-reset {{ color: {default_fg}; }}\n"
+    ));
+    css.push_str(&format!(
+        ".{CLASS_PREFIX}-bg-reset {{ background-color: {default_bg}; }}\n"
+    ));
+    let indexed = (0..16).map(|i| {
+        (
+            i.to_string().into(),
+            p.to_hex_str(Base16Color::from_index(i)),
+        )
+    });
+    let named = NAMED_COLORS
+        .iter()
+        .map(|(_, suffix, color)| (Cow::Borrowed(*suffix), Cow::Borrowed(*color)));
+    for (suffix, color) in indexed.chain(named) {
+        css.push_str(&format!(
+            ".{CLASS_PREFIX}-fg-{suffix} {{ color: {color}; }}\n"
+        ));
+        css.push_str(&format!(
+            ".{CLASS_PREFIX}-bg-{suffix} {{ background-color: {color}; }}\n"
+        ));
+    }
+    for (_, suffix, decl) in MODIFIER_CSS {
+        css.push_str(&format!(".{CLASS_PREFIX}-{suffix} {{ {decl} }}\n"));
+    }
+    css
+}
+
+/// Returns the rules that lay out the terminal for the given (scaled) font settings and the given
+/// offset of the grid from the top of the page. These are kept in the theme element rather than in
+/// `style` attributes so that a nonce-based CSP allows them.
+fn layout_css(config: &TermConfig, grid_offset: f64) -> String {
+    format!(
+        "\
+.{TERM_CLASS} {{ width: fit-content; block-size: fit-content; margin: auto; position: relative; {} }}
+.{GRID_CLASS} {{ margin-top: {grid_offset}px; }}
+.{LINE_CLASS} {{ {} }}
+.{LINK_CLASS} {{ text-decoration: none; }}
+.{CURSOR_CLASS} {{ position: absolute; inset: 0; background: currentColor; mix-blend-mode: difference; pointer-events: none; }}
+.{INPUT_CLASS} {{ position: absolute; inset: 0; width: 100%; height: 100%; padding: 0; border: 0; margin: 0; opacity: 0; resize: none; overflow: hidden; caret-color: transparent; font: inherit; }}
+.{NO_SELECT_CLASS} {{ user-select: none; -webkit-user-select: none; }}
+.{IMAGE_CLASS} {{ width: 100%; height: 100%; object-fit: contain; }}
+",
+        config.container_css(),
+        config.line_css()
+    )
+}
+
+fn to_css_color(p: &TermPalette, c: Color) -> Option<Cow<'static, str>> {
     match c {
         Color::Reset => None,
-        Color::Black => Some("black".into()),
-        Color::Red => Some("red".into()),
-        Color::Green => Some("green".into()),
-        Color::Yellow => Some("yellow".into()),
-        Color::Blue => Some("blue".into()),
-        Color::Magenta => Some("magenta".into()),
-        Color::Cyan => Some("cyan".into()),
-        Color::Gray => Some("gray".into()),
-        Color::DarkGray => Some("darkgray".into()),
-        Color::LightRed => Some("#de2b56".into()),
-        Color::LightGreen => Some("lightgreen".into()),
-        Color::LightYellow => Some("LightGoldenRodYellow".into()),
-        Color::LightBlue => Some("LightSkyBlue".into()),
-        Color::LightMagenta => Some("#ff00ff".into()),
-        Color::LightCyan => Some("lightcyan".into()),
-        Color::White => Some("white".into()),
-        Color::Rgb(r, g, b) => Some(format!("#{r:X}{g:X}{b:X}").into()),
-        Color::Indexed(i) => Some(p.to_hex_str(Base16Color::from_index(i)).into()),
+        Color::Rgb(r, g, b) => Some(rgb_css(r, g, b).into()),
+        Color::Indexed(i) if i >= 16 => {
+            let (r, g, b) = extended_rgb(i);
+            Some(rgb_css(r, g, b).into())
+        }
+        Color::Indexed(i) => Some(p.to_hex_str(Base16Color::from_index(i))),
+        c => named_color(c).map(|(_, css)| css.into()),
+    }
+}
+
+/// Returns the CSS class suffix and the CSS color for one of ratatui's named colors.
+fn named_color(c: Color) -> Option<(&'static str, &'static str)> {
+    NAMED_COLORS
+        .iter()
+        .find(|(color, _, _)| *color == c)
+        .map(|(_, suffix, css)| (*suffix, *css))
+}
+
+/// Converts an RGB color into a CSS hex color.
+fn rgb_css(r: u8, g: u8, b: u8) -> String {
+    format!("#{r:02X}{g:02X}{b:02X}")
+}
+
+/// Returns the RGB value of one of the extended indexed colors (16 through 255), which lie outside
+/// of the base16 palette. These follow xterm: a 6x6x6 color cube followed by a grayscale ramp.
+fn extended_rgb(i: u8) -> (u8, u8, u8) {
+    const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+    match i {
+        16..=231 => {
+            let i = i - 16;
+            (
+                LEVELS[(i / 36) as usize],
+                LEVELS[(i / 6 % 6) as usize],
+                LEVELS[(i % 6) as usize],
+            )
+        }
+        _ => {
+            let level = 8 + 10 * i.saturating_sub(232);
+            (level, level, level)
+        }
+    }
+}
+
+/// Returns the size of the area that the terminal can fill: the window or, on mobile, the screen.
+fn available_size() -> (f64, f64) {
+    if is_mobile() {
+        let (w, h) = get_raw_screen_size();
+        (w as f64, h as f64)
+    } else {
+        let (w, h) = get_raw_window_size();
+        (w as f64, h as f64)
+    }
+}
+
+/// Splits a line of cells into spans of text that share a style.
+fn line_spans(line: &[Cell]) -> Vec<TermSpan> {
+    let mut digest = Vec::new();
+    let Some(cell) = line.first() else {
+        return digest;
+    };
+    let mut fg = cell.fg;
+    let mut bg = cell.bg;
+    let mut mods = cell.modifier;
+    let mut text = String::with_capacity(line.len());
+    let to_span = |fg, bg, mods: Modifier, text: &str| {
+        if mods.contains(HYDRATION) {
+            TermSpan::Dehydrated(DehydratedSpan::new(fg, bg, mods, text.to_owned()))
+        } else {
+            TermSpan::Plain((fg, bg), mods, text.to_owned())
+        }
+    };
+    // The number of cells that are covered by the previous wide character
+    let mut covered = 0;
+    for c in line {
+        // Ratatui leaves placeholder cells after wide characters. These are hidden by the wide
+        // character and must not be rendered.
+        if covered > 0 {
+            covered -= 1;
+            continue;
+        }
+        covered = c.symbol().width().saturating_sub(1);
+        if fg != c.fg || bg != c.bg || mods != c.modifier {
+            // Create a new node, clear the text buffer, update the foreground/background
+            if !text.is_empty() {
+                digest.push(to_span(fg, bg, mods, &text));
+            }
+            mods = c.modifier;
+            fg = c.fg;
+            bg = c.bg;
+            text.clear();
+        }
+        match c.symbol() {
+            "" => text.push(' '),
+            symbol => text.push_str(symbol),
+        }
+    }
+    if !text.is_empty() {
+        digest.push(to_span(fg, bg, mods, &text));
     }
+    digest
+}
+
+/// Measures the width and height of a cell by rendering a hidden line of text with the given font
+/// settings. If this isn't possible, the cell size is estimated from the settings.
+fn measure_cell_size(config: &TermConfig) -> (f64, f64) {
+    usable_cell_size(probe_cell_size(config), config)
+}
+
+/// Returns the measured cell size if it is usable (i.e. positive and finite). Otherwise, the cell
+/// size is estimated from the settings or, if that isn't usable either, from the default settings.
+/// An unusable cell size would otherwise yield an enormous or empty grid.
+fn usable_cell_size(measured: Option<(f64, f64)>, config: &TermConfig) -> (f64, f64) {
+    let is_usable = |(w, h): &(f64, f64)| w.is_finite() && h.is_finite() && *w > 0.0 && *h > 0.0;
+    measured
+        .filter(is_usable)
+        .or_else(|| Some(config.estimated_cell_size()).filter(is_usable))
+        .unwrap_or_else(|| TermConfig::default().estimated_cell_size())
+}
+
+/// Renders a hidden line of text with the given font settings and returns the size of one of its
+/// cells. Returns `None` if the line can't be rendered.
+fn probe_cell_size(config: &TermConfig) -> Option<(f64, f64)> {
+    const PROBE_LEN: usize = 32;
+
+    let document = web_sys::window()?.document()?;
+    let (body, probe) = document.body().zip(document.create_element("pre").ok())?;
+    let style = format!(
+        "position: absolute; visibility: hidden; margin: 0px; {} {}",
+        config.container_css(),
+        config.line_css()
+    );
+    // Styles set through the CSSOM aren't subject to the page's CSP, unlike `style` attributes
+    if let Some(probe) = probe.dyn_ref::<HtmlElement>() {
+        probe.style().set_css_text(&style);
+    }
+    probe.set_text_content(Some(&"0".repeat(PROBE_LEN)));
+    body.append_child(&probe).ok()?;
+    let width = probe.get_bounding_client_rect().width() / PROBE_LEN as f64;
+    probe.remove();
+    Some((width, config.line_height_px()))
 }
 
 /// Calculates the number of characters that can fit in the window.
@@ -478,15 +1432,9 @@ impl ToIndexedColor for Base16Accent {
 
 /// Extends a CSS style string to include the necessary segments for the current modifiers.
 fn extend_css(mods: Modifier, css: &mut String) {
-    if mods.contains(Modifier::BOLD) {
-        css.push_str(" font-weight: bolder;");
-    }
-    if mods.contains(Modifier::ITALIC) {
-        css.push_str(" font-style: oblique;");
-    }
-
-    if mods.contains(Modifier::UNDERLINED) {
-        css.push_str(" text-decoration: underline;");
+    for (_, _, decl) in MODIFIER_CSS.iter().filter(|(m, _, _)| mods.contains(*m)) {
+        css.push(' ');
+        css.push_str(decl);
     }
 }
 
@@ -494,3 +1442,273 @@ fn extend_css(mods: Modifier, css: &mut String) {
 pub(crate) fn is_mobile() -> bool {
     get_raw_screen_size().0 < 550
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pads_rgb_channels() {
+        assert_eq!(rgb_css(1, 2, 3), "#010203");
+        assert_eq!(rgb_css(0xAB, 0xCD, 0xEF), "#ABCDEF");
+    }
+
+    #[test]
+    fn maps_extended_colors() {
+        assert_eq!(extended_rgb(16), (0, 0, 0));
+        assert_eq!(extended_rgb(196), (255, 0, 0));
+        assert_eq!(extended_rgb(231), (255, 255, 255));
+        assert_eq!(extended_rgb(232), (8, 8, 8));
+        assert_eq!(extended_rgb(255), (238, 238, 238));
+    }
+
+    #[test]
+    fn renders_without_a_web_terminal() {
+        let mut backend = YewBackend::headless(4, 2);
+        let mut cell = Cell::default();
+        cell.set_symbol("x");
+        backend.draw([(1, 1, &cell)].into_iter()).unwrap();
+        backend.set_cursor_position((2, 1));
+        assert_eq!(backend.get_cursor().unwrap(), (2, 1));
+        backend.flush().unwrap();
+        assert_eq!(backend.pre_hydrated.len(), 2);
+        assert!(matches!(backend.view(), Html::VTag(_)));
+        assert!(backend.pre_hydrated.is_empty());
+    }
+
+    #[test]
+    fn keeps_embedded_content_in_its_own_list() {
+        let mut backend = YewBackend::headless(4, 2);
+        let mut cell = Cell::default();
+        cell.set_symbol("x").modifier = crate::embed::EMBED;
+        backend.draw([(1, 0, &cell)].into_iter()).unwrap();
+        let selection = Selection {
+            mode: crate::selection::SelectionMode::Line,
+            anchor: (0, 1),
+            head: (3, 1),
+        };
+        backend.set_selection(Some(selection));
+        backend.flush().unwrap();
+        let Html::VTag(grid) = backend.hydrate(|_| {}, |region| region.html(Html::default()))
+        else {
+            panic!("the grid is not an element");
+        };
+        let Some(Html::VList(groups)) = grid.children() else {
+            panic!("the grid has no children");
+        };
+        // The lines, the embedded content, the selection, and the (hidden) cursor
+        assert_eq!(groups.len(), 4);
+        let Html::VList(overlays) = &groups[1] else {
+            panic!("the embedded content is not a list");
+        };
+        assert_eq!(overlays.len(), 1);
+        assert_eq!(overlays[0].key().map(|key| key.as_ref()), Some("1,0"));
+    }
+
+    /// Returns the keys of the lines in the backend's history.
+    fn history_keys(backend: &YewBackend) -> Vec<String> {
+        backend
+            .history
+            .iter()
+            .filter_map(|line| line.key().map(|key| key.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn pushes_history_only_when_kept() {
+        let mut backend = YewBackend::headless(4, 2);
+        let lines = Buffer::with_lines(vec!["one", "two"]);
+        backend.push_history(&lines);
+        assert!(backend.history.is_empty());
+        backend.set_scrollback(Some(10));
+        backend.push_history(&lines);
+        assert_eq!(history_keys(&backend), ["0", "1"]);
+        assert_eq!(backend.history_pushed(), 2);
+    }
+
+    #[test]
+    fn trims_history_to_scrollback() {
+        let mut backend = YewBackend::headless(4, 2);
+        backend.set_scrollback(Some(3));
+        backend.push_history(&Buffer::with_lines(vec!["a", "b", "c", "d", "e"]));
+        assert_eq!(history_keys(&backend), ["2", "3", "4"]);
+        assert_eq!(backend.history_pushed(), 5);
+        // Shrinking the scrollback drops the oldest lines that are already in the history
+        backend.set_scrollback(Some(1));
+        assert_eq!(history_keys(&backend), ["4"]);
+        assert_eq!(backend.history_pushed(), 5);
+        backend.set_scrollback(None);
+        assert_eq!(history_keys(&backend), ["4"]);
+    }
+
+    #[test]
+    fn pushes_lines_scrolled_off_by_append_lines() {
+        let mut backend = YewBackend::headless(4, 2);
+        backend.set_scrollback(Some(10));
+        backend.append_lines(3).unwrap();
+        assert_eq!(backend.get_cursor().unwrap(), (0, 1));
+        assert_eq!(history_keys(&backend), ["0", "1"]);
+    }
+
+    #[test]
+    fn rebuilds_history_view_only_after_changes() {
+        let mut backend = YewBackend::headless(4, 2);
+        backend.set_scrollback(Some(2));
+        backend.push_history(&Buffer::with_lines(vec!["a"]));
+        backend.flush().unwrap();
+        let _ = backend.view();
+        assert!(backend.history_view.is_some());
+        backend.flush().unwrap();
+        let _ = backend.view();
+        assert!(backend.history_view.is_some());
+        backend.push_history(&Buffer::with_lines(vec!["b", "c"]));
+        assert!(backend.history_view.is_none());
+    }
+
+    #[test]
+    fn falls_back_from_unusable_cell_sizes() {
+        let config = TermConfig::default();
+        let estimate = config.estimated_cell_size();
+        assert_eq!(usable_cell_size(Some((9.0, 20.0)), &config), (9.0, 20.0));
+        assert_eq!(usable_cell_size(None, &config), estimate);
+        for measured in [
+            (0.0, 20.0),
+            (9.0, 0.0),
+            (f64::NAN, 20.0),
+            (f64::INFINITY, 20.0),
+        ] {
+            assert_eq!(usable_cell_size(Some(measured), &config), estimate);
+        }
+        // A config that bypassed sanitization falls back to the default metrics
+        let broken = TermConfig {
+            font_size: 0.0,
+            ..TermConfig::default()
+        };
+        assert_eq!(usable_cell_size(Some((0.0, 0.0)), &broken), estimate);
+    }
+
+    #[test]
+    fn ignores_nan_font_scales() {
+        let mut backend = YewBackend::headless(4, 2);
+        assert!(!backend.set_font_scale(f64::NAN));
+        assert_eq!(backend.font_scale(), 1.0);
+    }
+
+    /// Fills a grid of the given size with `x`, clears it, and returns each line with `.` in place
+    /// of the cleared cells.
+    fn clear(
+        (width, height): (usize, usize),
+        cursor: (u16, u16),
+        clear_type: ClearType,
+    ) -> Vec<String> {
+        let mut cell = Cell::default();
+        cell.set_symbol("x");
+        let mut grid = vec![vec![cell; width]; height];
+        clear_cells(&mut grid, cursor, clear_type);
+        grid.iter()
+            .map(|line| {
+                line.iter()
+                    .map(|c| if c.symbol() == " " { '.' } else { 'x' })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn clears_everything() {
+        assert_eq!(clear((3, 2), (1, 1), ClearType::All), ["...", "..."]);
+    }
+
+    #[test]
+    fn clears_after_cursor() {
+        assert_eq!(
+            clear((3, 3), (1, 1), ClearType::AfterCursor),
+            ["xxx", "x..", "..."]
+        );
+        assert_eq!(
+            clear((3, 3), (0, 0), ClearType::AfterCursor),
+            ["...", "...", "..."]
+        );
+        assert_eq!(
+            clear((3, 3), (2, 2), ClearType::AfterCursor),
+            ["xxx", "xxx", "xx."]
+        );
+        // Past the end of the line, only the following lines are cleared
+        assert_eq!(
+            clear((3, 3), (5, 1), ClearType::AfterCursor),
+            ["xxx", "xxx", "..."]
+        );
+        assert_eq!(
+            clear((3, 3), (0, 5), ClearType::AfterCursor),
+            ["xxx", "xxx", "xxx"]
+        );
+    }
+
+    #[test]
+    fn clears_before_cursor() {
+        assert_eq!(
+            clear((3, 3), (1, 1), ClearType::BeforeCursor),
+            ["...", "..x", "xxx"]
+        );
+        assert_eq!(
+            clear((3, 3), (0, 0), ClearType::BeforeCursor),
+            [".xx", "xxx", "xxx"]
+        );
+        assert_eq!(
+            clear((3, 3), (2, 2), ClearType::BeforeCursor),
+            ["...", "...", "..."]
+        );
+        assert_eq!(
+            clear((3, 3), (5, 1), ClearType::BeforeCursor),
+            ["...", "...", "xxx"]
+        );
+        assert_eq!(
+            clear((3, 3), (0, 5), ClearType::BeforeCursor),
+            ["...", "...", "..."]
+        );
+    }
+
+    #[test]
+    fn clears_current_line() {
+        assert_eq!(
+            clear((3, 3), (1, 1), ClearType::CurrentLine),
+            ["xxx", "...", "xxx"]
+        );
+        assert_eq!(
+            clear((3, 3), (2, 2), ClearType::CurrentLine),
+            ["xxx", "xxx", "..."]
+        );
+        assert_eq!(
+            clear((3, 3), (0, 5), ClearType::CurrentLine),
+            ["xxx", "xxx", "xxx"]
+        );
+    }
+
+    #[test]
+    fn clears_until_new_line() {
+        assert_eq!(
+            clear((3, 3), (1, 1), ClearType::UntilNewLine),
+            ["xxx", "x..", "xxx"]
+        );
+        assert_eq!(
+            clear((3, 3), (0, 0), ClearType::UntilNewLine),
+            ["...", "xxx", "xxx"]
+        );
+        assert_eq!(
+            clear((3, 3), (2, 2), ClearType::UntilNewLine),
+            ["xxx", "xxx", "xx."]
+        );
+        assert_eq!(
+            clear((3, 3), (5, 1), ClearType::UntilNewLine),
+            ["xxx", "xxx", "xxx"]
+        );
+    }
+
+    #[test]
+    fn inlines_extended_colors_in_class_mode() {
+        let (classes, style) =
+            to_css_classes(Color::Indexed(196), Color::Indexed(3), Modifier::BOLD);
+        assert_eq!(classes.as_deref(), Some("webatui-bg-3 webatui-bold"));
+        assert_eq!(style.as_deref(), Some("color: #FF0000; "));
+    }
+}
@@ -0,0 +1,165 @@
+use std::io::Result;
+
+use ratatui::{
+    backend::{Backend, ClearType},
+    buffer::Cell,
+    style::{Color, Modifier},
+};
+
+use crate::backend::YewBackend;
+
+/// Implements the `Backend` trait of the given ratatui release, which is imported under the given
+/// crate name. Newer releases replaced the tuple-based cursor API with `Position` and return the
+/// terminal's size as a `Size`, so each one is implemented on top of the ratatui 0.26
+/// implementation. Cells are converted as they are drawn.
+macro_rules! impl_backend {
+    ($version:ident, $ratatui:ident) => {
+        mod $version {
+            use $ratatui::{
+                backend::{ClearType as NewClearType, WindowSize},
+                buffer::Cell as NewCell,
+                layout::{Position, Size},
+                style::Color as NewColor,
+            };
+
+            use super::*;
+
+            impl $ratatui::backend::Backend for YewBackend {
+                fn draw<'a, I>(&mut self, content: I) -> Result<()>
+                where
+                    I: Iterator<Item = (u16, u16, &'a NewCell)>,
+                {
+                    let cells: Vec<_> = content.map(|(x, y, c)| (x, y, cell(c))).collect();
+                    Backend::draw(self, cells.iter().map(|(x, y, c)| (*x, *y, c)))
+                }
+
+                fn append_lines(&mut self, n: u16) -> Result<()> {
+                    Backend::append_lines(self, n)
+                }
+
+                fn hide_cursor(&mut self) -> Result<()> {
+                    Backend::hide_cursor(self)
+                }
+
+                fn show_cursor(&mut self) -> Result<()> {
+                    Backend::show_cursor(self)
+                }
+
+                fn get_cursor_position(&mut self) -> Result<Position> {
+                    let (x, y) = Backend::get_cursor(self)?;
+                    Ok(Position::new(x, y))
+                }
+
+                fn set_cursor_position<P: Into<Position>>(&mut self, position: P) -> Result<()> {
+                    let Position { x, y } = position.into();
+                    Backend::set_cursor(self, x, y)
+                }
+
+                fn clear(&mut self) -> Result<()> {
+                    Backend::clear(self)
+                }
+
+                fn clear_region(&mut self, clear_type: NewClearType) -> Result<()> {
+                    let clear_type = match clear_type {
+                        NewClearType::All => ClearType::All,
+                        NewClearType::AfterCursor => ClearType::AfterCursor,
+                        NewClearType::BeforeCursor => ClearType::BeforeCursor,
+                        NewClearType::CurrentLine => ClearType::CurrentLine,
+                        NewClearType::UntilNewLine => ClearType::UntilNewLine,
+                    };
+                    Backend::clear_region(self, clear_type)
+                }
+
+                fn size(&self) -> Result<Size> {
+                    let area = Backend::size(self)?;
+                    Ok(Size::new(area.width, area.height))
+                }
+
+                fn window_size(&mut self) -> Result<WindowSize> {
+                    let size = Backend::window_size(self)?;
+                    Ok(WindowSize {
+                        columns_rows: Size::new(size.columns_rows.width, size.columns_rows.height),
+                        pixels: Size::new(size.pixels.width, size.pixels.height),
+                    })
+                }
+
+                fn flush(&mut self) -> Result<()> {
+                    Backend::flush(self)
+                }
+            }
+
+            /// Converts a cell from this release into a ratatui 0.26 cell.
+            fn cell(cell: &NewCell) -> Cell {
+                let mut digest = Cell::default();
+                digest.set_symbol(cell.symbol());
+                digest.fg = color(cell.fg);
+                digest.bg = color(cell.bg);
+                digest.modifier = Modifier::from_bits_truncate(cell.modifier.bits());
+                digest.skip = cell.skip;
+                digest
+            }
+
+            /// Converts a color from this release into a ratatui 0.26 color.
+            fn color(color: NewColor) -> Color {
+                match color {
+                    NewColor::Reset => Color::Reset,
+                    NewColor::Black => Color::Black,
+                    NewColor::Red => Color::Red,
+                    NewColor::Green => Color::Green,
+                    NewColor::Yellow => Color::Yellow,
+                    NewColor::Blue => Color::Blue,
+                    NewColor::Magenta => Color::Magenta,
+                    NewColor::Cyan => Color::Cyan,
+                    NewColor::Gray => Color::Gray,
+                    NewColor::DarkGray => Color::DarkGray,
+                    NewColor::LightRed => Color::LightRed,
+                    NewColor::LightGreen => Color::LightGreen,
+                    NewColor::LightYellow => Color::LightYellow,
+                    NewColor::LightBlue => Color::LightBlue,
+                    NewColor::LightMagenta => Color::LightMagenta,
+                    NewColor::LightCyan => Color::LightCyan,
+                    NewColor::White => Color::White,
+                    NewColor::Rgb(r, g, b) => Color::Rgb(r, g, b),
+                    NewColor::Indexed(i) => Color::Indexed(i),
+                }
+            }
+
+            #[cfg(test)]
+            mod tests {
+                use yew::Html;
+                use $ratatui::{widgets::Paragraph, Terminal};
+
+                use super::*;
+                use crate::selection::{Selection, SelectionMode};
+
+                #[test]
+                fn draws_through_terminal() {
+                    let mut terminal = Terminal::new(YewBackend::headless(8, 2)).unwrap();
+                    terminal
+                        .draw(|frame| {
+                            let text = Paragraph::new("hello\nworld");
+                            frame.render_widget(text, frame.area());
+                            frame.set_cursor_position(Position::new(3, 1));
+                        })
+                        .unwrap();
+                    let backend = terminal.backend_mut();
+                    let cursor = backend.get_cursor_position();
+                    assert_eq!((cursor.x, cursor.y), (3, 1));
+                    let all = Selection {
+                        mode: SelectionMode::Block,
+                        anchor: (0, 0),
+                        head: (7, 1),
+                    };
+                    backend.set_selection(Some(all));
+                    assert_eq!(backend.selected_text().as_deref(), Some("hello\nworld"));
+                    assert!(matches!(backend.view(), Html::VTag(_)));
+                }
+            }
+        }
+    };
+}
+
+#[cfg(feature = "ratatui_0_28")]
+impl_backend!(v0_28, ratatui_0_28);
+#[cfg(feature = "ratatui_0_29")]
+impl_backend!(v0_29, ratatui_0_29);
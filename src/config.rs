@@ -0,0 +1,128 @@
+/// The font settings that the terminal applies to its container. These are also used to calculate
+/// the size of the character grid, so the terminal does not rely on the page's CSS. Values that
+/// can't be used (e.g. a font size of zero) are replaced by their defaults.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TermConfig {
+    /// The CSS font family, e.g. `"'Source Code Pro', monospace"`. If `None`, the font family is
+    /// left to the page's CSS. Regardless, the font should be monospaced.
+    pub font_family: Option<String>,
+    /// The font size, in pixels.
+    pub font_size: f64,
+    /// The line height, as a multiple of the font size.
+    pub line_height: f64,
+    /// The additional spacing between characters, in pixels.
+    pub letter_spacing: f64,
+}
+
+impl TermConfig {
+    /// The width of a glyph relative to the font size. This is used to estimate the width of a
+    /// cell when it can't be measured. Most monospace fonts are close to this.
+    const GLYPH_WIDTH_RATIO: f64 = 0.6;
+
+    /// Returns a copy of these settings with the font size and letter spacing multiplied by the
+    /// given scale.
+    pub fn scaled(&self, scale: f64) -> Self {
+        Self {
+            font_family: self.font_family.clone(),
+            font_size: self.font_size * scale,
+            line_height: self.line_height,
+            letter_spacing: self.letter_spacing * scale,
+        }
+    }
+
+    /// Returns a copy of these settings in which each value that can't be used is replaced by its
+    /// default. The font size and line height must be positive and finite, and the letter spacing
+    /// must be finite.
+    pub(crate) fn sanitized(mut self) -> Self {
+        let default = Self::default();
+        if !(self.font_size.is_finite() && self.font_size > 0.0) {
+            self.font_size = default.font_size;
+        }
+        if !(self.line_height.is_finite() && self.line_height > 0.0) {
+            self.line_height = default.line_height;
+        }
+        if !self.letter_spacing.is_finite() {
+            self.letter_spacing = default.letter_spacing;
+        }
+        self
+    }
+
+    /// Returns the height of a line (and, therefore, of a cell), in pixels.
+    pub fn line_height_px(&self) -> f64 {
+        self.font_size * self.line_height
+    }
+
+    /// Returns an estimate of the width and height of a cell, in pixels.
+    pub fn estimated_cell_size(&self) -> (f64, f64) {
+        let width = self.font_size * Self::GLYPH_WIDTH_RATIO + self.letter_spacing;
+        (width, self.line_height_px())
+    }
+
+    /// Returns the inline CSS for the terminal's container.
+    pub(crate) fn container_css(&self) -> String {
+        let mut css = format!(
+            "font-size: {}px; line-height: {}px; letter-spacing: {}px;",
+            self.font_size,
+            self.line_height_px(),
+            self.letter_spacing
+        );
+        if let Some(family) = self.font_family.as_deref() {
+            css.push_str(&format!(" font-family: {family};"));
+        }
+        css
+    }
+
+    /// Returns the inline CSS for each line of the terminal. Lines inherit the font settings from
+    /// the container rather than the browser's defaults for `<pre>` tags.
+    pub(crate) fn line_css(&self) -> &'static str {
+        if self.font_family.is_some() {
+            "margin: 0px; font-size: inherit; line-height: inherit; letter-spacing: inherit; font-family: inherit;"
+        } else {
+            "margin: 0px; font-size: inherit; line-height: inherit; letter-spacing: inherit;"
+        }
+    }
+}
+
+impl Default for TermConfig {
+    fn default() -> Self {
+        Self {
+            font_family: None,
+            font_size: 16.0,
+            line_height: 1.25,
+            letter_spacing: 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replaces_unusable_values() {
+        let config = TermConfig {
+            font_family: None,
+            font_size: 0.0,
+            line_height: f64::NAN,
+            letter_spacing: f64::INFINITY,
+        };
+        assert_eq!(config.sanitized(), TermConfig::default());
+        let config = TermConfig {
+            font_size: -4.0,
+            line_height: -1.0,
+            ..TermConfig::default()
+        };
+        assert_eq!(config.sanitized(), TermConfig::default());
+    }
+
+    #[test]
+    fn keeps_usable_values() {
+        let config = TermConfig {
+            font_family: Some("monospace".to_owned()),
+            font_size: 12.5,
+            line_height: 1.0,
+            letter_spacing: -0.5,
+        };
+        assert_eq!(config.clone().sanitized(), config);
+    }
+}
@@ -0,0 +1,288 @@
+use ratatui::{
+    buffer::Cell,
+    prelude::Rect,
+    style::{Modifier, Styled},
+};
+use yew::{html, Html};
+
+use crate::backend::{EMBED_CLASS, IMAGE_CLASS};
+
+/// When added as a modifier to a style, the styled element is marked as "to be embedded" by the
+/// rendering backend. Each rectangular region of marked cells is given to the app (along with the
+/// text rendered into it), which can then place content, like an image, over that region.
+pub const EMBED: Modifier = Modifier::RAPID_BLINK;
+
+/// A rectangular region of the terminal that was marked with the [`EMBED`] modifier. The app can
+/// place content over the region. If it does not, the region's text is rendered as usual.
+#[derive(Debug)]
+pub struct EmbedRegion {
+    area: Rect,
+    text: String,
+    content: Option<Embedded>,
+}
+
+/// The content that an app has placed over a region.
+#[derive(Debug)]
+enum Embedded {
+    Image(ImageSource),
+    Html(Html),
+}
+
+/// The source of an image that is placed over a region of the terminal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImageSource {
+    /// An image that is loaded from a URL.
+    Url(String),
+    /// An image that is held in memory, along with its MIME type (e.g. `image/png`).
+    Bytes {
+        /// The MIME type of the image.
+        mime: String,
+        /// The encoded image.
+        data: Vec<u8>,
+    },
+}
+
+impl EmbedRegion {
+    /// Returns the area of the terminal that the region covers.
+    pub fn area(&self) -> Rect {
+        self.area
+    }
+
+    /// Returns the text that was rendered into the region. Each row of the region is separated by
+    /// a newline, and surrounding whitespace is trimmed.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Places an image over the region. The image is scaled to fit inside the region while
+    /// keeping its aspect ratio, and the rest of the region is filled with the terminal's background
+    /// color. The region's text is used as the image's alt text.
+    pub fn image(&mut self, source: ImageSource) {
+        let _ = self.content.insert(Embedded::Image(source));
+    }
+
+    /// Places arbitrary HTML, such as an `<input>` or a `<video>`, over the region. The HTML is
+    /// placed in a container that exactly covers the region's cells and is resized along with the
+    /// grid, so the node should size itself relative to that container (e.g. `width: 100%`).
+    pub fn html(&mut self, node: Html) {
+        let _ = self.content.insert(Embedded::Html(node));
+    }
+
+    /// Renders the region's content, positioned over the region's cells. Returns `None` if the
+    /// app did not place anything over the region. The content is keyed by the region's top-left
+    /// cell, so it is kept between frames as long as the region doesn't move.
+    pub(crate) fn render(self, (cell_w, cell_h): (f64, f64)) -> Option<Html> {
+        let Rect {
+            x,
+            y,
+            width,
+            height,
+        } = self.area;
+        let style = format!(
+            "position: absolute; left: {}px; top: {}px; width: {}px; height: {}px; overflow: hidden;",
+            x as f64 * cell_w,
+            y as f64 * cell_h,
+            width as f64 * cell_w,
+            height as f64 * cell_h,
+        );
+        let inner = match self.content? {
+            Embedded::Image(source) => {
+                let src = match source {
+                    ImageSource::Url(url) => url,
+                    ImageSource::Bytes { mime, data } => {
+                        format!("data:{mime};base64,{}", to_base64(&data))
+                    }
+                };
+                html! {
+                    <img { src } alt={ self.text } class={ IMAGE_CLASS } />
+                }
+            }
+            Embedded::Html(node) => node,
+        };
+        let key = format!("{x},{y}");
+        Some(html! { <div { key } class={ EMBED_CLASS } { style }>{ inner }</div> })
+    }
+}
+
+/// Finds all of the rectangular regions of cells that are marked with the [`EMBED`] modifier. A
+/// region is grown right along its first row and then down for as long as the following rows are
+/// marked over exactly the same columns.
+pub(crate) fn find_regions(buffer: &[Vec<Cell>]) -> Vec<EmbedRegion> {
+    let is_marked = |x: usize, y: usize| {
+        buffer
+            .get(y)
+            .and_then(|line| line.get(x))
+            .is_some_and(|c| c.modifier.contains(EMBED))
+    };
+    let mut digest: Vec<EmbedRegion> = Vec::new();
+    for (y, line) in buffer.iter().enumerate() {
+        for x in 0..line.len() {
+            let claimed = digest.iter().any(|r| {
+                let Rect {
+                    x: rx,
+                    y: ry,
+                    width,
+                    height,
+                } = r.area;
+                (rx as usize..(rx + width) as usize).contains(&x)
+                    && (ry as usize..(ry + height) as usize).contains(&y)
+            });
+            if claimed || !is_marked(x, y) {
+                continue;
+            }
+            let x_end = (x..line.len())
+                .find(|i| !is_marked(*i, y))
+                .unwrap_or(line.len());
+            let y_end = (y + 1..buffer.len())
+                .find(|j| {
+                    !(x..x_end).all(|i| is_marked(i, *j))
+                        || (x > 0 && is_marked(x - 1, *j))
+                        || is_marked(x_end, *j)
+                })
+                .unwrap_or(buffer.len());
+            let text = buffer[y..y_end]
+                .iter()
+                .map(|line| {
+                    line[x..x_end]
+                        .iter()
+                        .map(Cell::symbol)
+                        .collect::<String>()
+                        .trim()
+                        .to_owned()
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+                .trim()
+                .to_owned();
+            digest.push(EmbedRegion {
+                area: Rect::new(x as u16, y as u16, (x_end - x) as u16, (y_end - y) as u16),
+                text,
+                content: None,
+            });
+        }
+    }
+    digest
+}
+
+/// Encodes the given bytes as (padded) base64.
+fn to_base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut digest = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                digest.push(ALPHABET[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                digest.push('=');
+            }
+        }
+    }
+    digest
+}
+
+/// An abstraction to allow for method chain to mark a something as embeddable.
+pub trait NeedsEmbedding: Sized + Styled {
+    /// Marks a styled item as "to be embedded". This communicates to the backend that the
+    /// [`TerminalApp`](crate::TerminalApp) might place content, such as an image, over the area
+    /// that the item is rendered into.
+    ///
+    /// NOTE: Regions are found by looking for rectangles of marked cells, so the styled item
+    /// should fill the area that it is rendered into (e.g. a `Paragraph` or a `Block`).
+    fn to_embed(self) -> Self::Item {
+        let style = self.style().add_modifier(EMBED);
+        self.set_style(style)
+    }
+}
+
+impl<T> NeedsEmbedding for T where T: Styled {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a buffer from the given rows. Uppercase letters are marked for embedding.
+    fn buffer(rows: &[&str]) -> Vec<Vec<Cell>> {
+        rows.iter()
+            .map(|row| {
+                row.chars()
+                    .map(|c| {
+                        let mut cell = Cell::default();
+                        cell.set_char(c);
+                        if c.is_ascii_uppercase() {
+                            cell.modifier.insert(EMBED);
+                        }
+                        cell
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn regions(rows: &[&str]) -> Vec<(Rect, String)> {
+        find_regions(&buffer(rows))
+            .into_iter()
+            .map(|r| (r.area(), r.text().to_owned()))
+            .collect()
+    }
+
+    #[test]
+    fn encodes_base64() {
+        // The test vectors from RFC 4648
+        let vectors = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+        for (input, output) in vectors {
+            assert_eq!(to_base64(input.as_bytes()), output);
+        }
+    }
+
+    #[test]
+    fn finds_nothing_without_marks() {
+        assert!(regions(&[]).is_empty());
+        assert!(regions(&["abc", "def"]).is_empty());
+    }
+
+    #[test]
+    fn finds_a_rectangle() {
+        assert_eq!(
+            regions(&["..AB..", "..CD..", "......"]),
+            [(Rect::new(2, 0, 2, 2), "AB\nCD".to_owned())]
+        );
+    }
+
+    #[test]
+    fn finds_separate_regions() {
+        assert_eq!(
+            regions(&["AB.CD", "EF..."]),
+            [
+                (Rect::new(0, 0, 2, 2), "AB\nEF".to_owned()),
+                (Rect::new(3, 0, 2, 1), "CD".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn stops_at_rows_with_different_columns() {
+        assert_eq!(
+            regions(&["AB.", "CDE", ".F."]),
+            [
+                (Rect::new(0, 0, 2, 1), "AB".to_owned()),
+                (Rect::new(0, 1, 3, 1), "CDE".to_owned()),
+                (Rect::new(1, 2, 1, 1), "F".to_owned()),
+            ]
+        );
+    }
+}
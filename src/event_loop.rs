@@ -0,0 +1,389 @@
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    future::Future,
+    io::Result,
+    pin::pin,
+    rc::Rc,
+    task::{Poll, Waker},
+    time::Duration,
+};
+
+use futures::future::{self, Either, LocalBoxFuture};
+use ratatui::{
+    backend::{Backend, WindowSize},
+    buffer::{Buffer, Cell},
+    layout::Size,
+    prelude::Rect,
+    Frame, Terminal,
+};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::js_sys::Promise;
+use yew::{Callback, Context};
+
+use crate::{
+    input::{KeyEvent, KeyModifiers, MouseEvent, MouseEventKind, TermEvent},
+    viewport_area, ScrollMotion, TermContext, TerminalApp, WebTermMessage, WebTermProps,
+    WebTerminal,
+};
+
+/// The events that an [`EventLoop`] reads. These are modeled on crossterm's `Event` type, but a
+/// `match` over crossterm's events still needs two edits to be ported:
+/// - Key events have no `kind` or `state`, since only presses are reported. Guards like
+///   `key.kind == KeyEventKind::Press` can be dropped.
+/// - Horizontal scrolling is not reported, so there is no `ScrollLeft` or `ScrollRight`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// The page gained the focus.
+    FocusGained,
+    /// The page lost the focus.
+    FocusLost,
+    /// A key was pressed.
+    Key(KeyEvent),
+    /// The mouse was used over the terminal. Scrolling is reported as happening over the top-left
+    /// cell since the browser's scroll events are not tied to a cell.
+    Mouse(MouseEvent),
+    /// Text was pasted into the terminal.
+    Paste(String),
+    /// The terminal was resized to the given number of columns and rows.
+    Resize(u16, u16),
+}
+
+/// The handle that an event loop uses to draw to the terminal and to read events. This takes the
+/// place of both the `Terminal` and crossterm's `event::read` in a native app.
+pub struct EventLoop {
+    terminal: Terminal<FrameBackend>,
+    shared: Rc<RefCell<Shared>>,
+    redraw: Callback<()>,
+}
+
+/// A [`TerminalApp`] that hosts an app that is written as an event loop, i.e. an async function
+/// that repeatedly draws and then waits for the next event:
+/// ```no_run
+/// use ratatui::widgets::Paragraph;
+/// use webatui::event_loop::{run_event_loop, Event};
+///
+/// run_event_loop(|mut term| async move {
+///     let mut count = 0;
+///     loop {
+///         term.draw(|frame| {
+///             let para = Paragraph::new(format!("{count} keys pressed"));
+///             frame.render_widget(para, frame.size());
+///         })
+///         .unwrap();
+///         if let Event::Key(_) = term.read().await {
+///             count += 1;
+///         }
+///     }
+/// });
+/// ```
+/// The loop runs on the browser's event loop, so it must `await` between frames rather than block.
+pub struct EventLoopApp {
+    shared: Rc<RefCell<Shared>>,
+    main: Option<Box<dyn FnOnce(EventLoop) -> LocalBoxFuture<'static, ()>>>,
+}
+
+/// The messages that drive an [`EventLoopApp`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventLoopMessage {
+    /// Starts the event loop.
+    Start,
+    /// The event loop has drawn a new frame.
+    Drawn,
+    /// The event loop has returned.
+    Finished,
+}
+
+/// The state that is shared between the event loop and the app that hosts it.
+#[derive(Default)]
+struct Shared {
+    /// The last frame that the event loop drew.
+    buffer: Buffer,
+    /// The location of the cursor in the last frame, if it was shown.
+    cursor: Option<(u16, u16)>,
+    /// The current size of the terminal.
+    size: Rect,
+    /// The events that the event loop has not read yet.
+    events: VecDeque<Event>,
+    /// Wakes the event loop when an event arrives.
+    waker: Option<Waker>,
+}
+
+/// The backend of the event loop's terminal. The event loop draws into this backend, and the
+/// hosting app copies the resulting frame into the real terminal.
+struct FrameBackend {
+    buffer: Buffer,
+    cursor: (u16, u16),
+    cursor_visible: bool,
+}
+
+impl EventLoop {
+    /// Draws a frame, just like `Terminal::draw`. The frame is shown on the next animation frame.
+    pub fn draw<F>(&mut self, f: F) -> Result<()>
+    where
+        F: FnOnce(&mut Frame<'_>),
+    {
+        let size = self.shared.borrow().size;
+        // The terminal notices the new size and resizes itself during the draw
+        self.terminal.backend_mut().buffer.resize(size);
+        self.terminal.draw(f)?;
+        let backend = self.terminal.backend();
+        let mut shared = self.shared.borrow_mut();
+        shared.buffer = backend.buffer.clone();
+        shared.cursor = backend.cursor_visible.then_some(backend.cursor);
+        drop(shared);
+        self.redraw.emit(());
+        Ok(())
+    }
+
+    /// Returns the current size of the terminal.
+    pub fn size(&self) -> Rect {
+        self.shared.borrow().size
+    }
+
+    /// Waits for the next event, like crossterm's `event::read`.
+    pub async fn read(&mut self) -> Event {
+        future::poll_fn(|cx| {
+            let mut shared = self.shared.borrow_mut();
+            match shared.events.pop_front() {
+                Some(event) => Poll::Ready(event),
+                None => {
+                    shared.waker = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+            }
+        })
+        .await
+    }
+
+    /// Returns the next event if one has already arrived.
+    pub fn try_read(&mut self) -> Option<Event> {
+        self.shared.borrow_mut().events.pop_front()
+    }
+
+    /// Waits for the next event for at most the given duration. This replaces the pairing of
+    /// crossterm's `event::poll` and `event::read` that is used by apps that tick.
+    pub async fn read_timeout(&mut self, timeout: Duration) -> Option<Event> {
+        match future::select(pin!(self.read()), pin!(sleep(timeout))).await {
+            Either::Left((event, _)) => Some(event),
+            Either::Right(((), _)) => None,
+        }
+    }
+}
+
+impl EventLoopApp {
+    /// Creates an app that runs the given event loop once the terminal is created.
+    pub fn new<F, Fut>(main: F) -> Self
+    where
+        F: 'static + FnOnce(EventLoop) -> Fut,
+        Fut: 'static + Future<Output = ()>,
+    {
+        Self {
+            shared: Rc::default(),
+            main: Some(Box::new(move |term| Box::pin(main(term)))),
+        }
+    }
+
+    /// Returns the props for a terminal that runs the given event loop. Text input and mouse
+    /// capture are enabled so that the event loop receives key and mouse events.
+    pub fn props<F, Fut>(main: F) -> WebTermProps<Self>
+    where
+        F: 'static + FnOnce(EventLoop) -> Fut,
+        Fut: 'static + Future<Output = ()>,
+    {
+        // The props only create the app once, so the event loop is handed over on the first call
+        let main = RefCell::new(Some(EventLoopApp::new(main)));
+        WebTermProps::new_with_factory(move || {
+            main.take().unwrap_or_else(|| Self {
+                shared: Rc::default(),
+                main: None,
+            })
+        })
+        .with_text_input(true)
+        .with_mouse_capture(true)
+    }
+
+    /// Passes an event to the event loop.
+    fn push(&self, event: Event) {
+        let mut shared = self.shared.borrow_mut();
+        shared.events.push_back(event);
+        if let Some(waker) = shared.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Launches the rendering process using the given event loop. See [`EventLoopApp`] for details.
+pub fn run_event_loop<F, Fut>(main: F)
+where
+    F: 'static + FnOnce(EventLoop) -> Fut,
+    Fut: 'static + Future<Output = ()>,
+{
+    yew::Renderer::<WebTerminal<EventLoopApp>>::with_props(EventLoopApp::props(main)).render();
+}
+
+impl TerminalApp for EventLoopApp {
+    type Message = EventLoopMessage;
+
+    fn setup(&mut self, ctx: &Context<WebTerminal<Self>>) {
+        ctx.link()
+            .send_message(WebTermMessage::Inner(EventLoopMessage::Start));
+    }
+
+    fn resize(&mut self, area: Rect) {
+        self.shared.borrow_mut().size = Rect::new(0, 0, area.width, area.height);
+        self.push(Event::Resize(area.width, area.height));
+    }
+
+    fn scroll(&mut self, scroll: ScrollMotion) -> bool {
+        let kind = match scroll {
+            ScrollMotion::Up => MouseEventKind::ScrollUp,
+            ScrollMotion::Down => MouseEventKind::ScrollDown,
+        };
+        self.push(Event::Mouse(MouseEvent {
+            kind,
+            column: 0,
+            row: 0,
+            modifiers: KeyModifiers::default(),
+        }));
+        false
+    }
+
+    fn event(&mut self, event: TermEvent) -> bool {
+        self.push(event.into());
+        false
+    }
+
+    fn update(&mut self, mut ctx: TermContext<'_, Self>, msg: Self::Message) -> bool {
+        match msg {
+            EventLoopMessage::Start => {
+                let Some(main) = self.main.take() else {
+                    return false;
+                };
+                let area = viewport_area(ctx.terminal());
+                // The event loop's terminal starts at the origin, wherever the viewport is
+                let size = Rect::new(0, 0, area.width, area.height);
+                self.shared.borrow_mut().size = size;
+                let term = EventLoop {
+                    terminal: Terminal::new(FrameBackend::new(size)).unwrap(),
+                    shared: Rc::clone(&self.shared),
+                    redraw: ctx
+                        .ctx()
+                        .link()
+                        .callback(|()| WebTermMessage::Inner(EventLoopMessage::Drawn)),
+                };
+                ctx.spawn(async move {
+                    main(term).await;
+                    EventLoopMessage::Finished
+                });
+                false
+            }
+            EventLoopMessage::Drawn => true,
+            // The last frame stays on screen
+            EventLoopMessage::Finished => false,
+        }
+    }
+
+    fn render(&self, area: Rect, frame: &mut Frame<'_>) {
+        // The event loop draws from the origin, so its frame is shifted onto the viewport
+        let shared = self.shared.borrow();
+        let drawn = shared.buffer.area;
+        for y in 0..drawn.height.min(area.height) {
+            for x in 0..drawn.width.min(area.width) {
+                *frame.buffer_mut().get_mut(area.x + x, area.y + y) =
+                    shared.buffer.get(drawn.x + x, drawn.y + y).clone();
+            }
+        }
+        if let Some((x, y)) = shared.cursor {
+            frame.set_cursor(area.x + x, area.y + y);
+        }
+    }
+}
+
+impl From<TermEvent> for Event {
+    fn from(value: TermEvent) -> Self {
+        match value {
+            TermEvent::Key(key) => Self::Key(key),
+            TermEvent::Mouse(mouse) => Self::Mouse(mouse),
+            TermEvent::Paste(text) => Self::Paste(text),
+            TermEvent::FocusGained => Self::FocusGained,
+            TermEvent::FocusLost => Self::FocusLost,
+        }
+    }
+}
+
+impl FrameBackend {
+    fn new(size: Rect) -> Self {
+        Self {
+            buffer: Buffer::empty(size),
+            cursor: (0, 0),
+            cursor_visible: false,
+        }
+    }
+}
+
+impl Backend for FrameBackend {
+    fn draw<'a, I>(&mut self, content: I) -> Result<()>
+    where
+        I: Iterator<Item = (u16, u16, &'a Cell)>,
+    {
+        let area = self.buffer.area;
+        for (x, y, cell) in content {
+            if x < area.right() && y < area.bottom() {
+                *self.buffer.get_mut(x, y) = cell.clone();
+            }
+        }
+        Ok(())
+    }
+
+    fn hide_cursor(&mut self) -> Result<()> {
+        self.cursor_visible = false;
+        Ok(())
+    }
+
+    fn show_cursor(&mut self) -> Result<()> {
+        self.cursor_visible = true;
+        Ok(())
+    }
+
+    fn get_cursor(&mut self) -> Result<(u16, u16)> {
+        Ok(self.cursor)
+    }
+
+    fn set_cursor(&mut self, x: u16, y: u16) -> Result<()> {
+        self.cursor = (x, y);
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        self.buffer.reset();
+        Ok(())
+    }
+
+    fn size(&self) -> Result<Rect> {
+        Ok(self.buffer.area)
+    }
+
+    fn window_size(&mut self) -> Result<WindowSize> {
+        let area = self.buffer.area;
+        Ok(WindowSize {
+            columns_rows: Size::new(area.width, area.height),
+            pixels: Size::new(0, 0),
+        })
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Waits for the given duration using the browser's timers.
+async fn sleep(duration: Duration) {
+    let millis = duration.as_millis().min(i32::MAX as u128) as i32;
+    let promise = Promise::new(&mut |resolve, _| {
+        if let Some(window) = web_sys::window() {
+            let _ = window.set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, millis);
+        }
+    });
+    let _ = JsFuture::from(promise).await;
+}
@@ -0,0 +1,260 @@
+use std::borrow::Cow;
+
+use yew::{html, Html};
+
+use crate::backend::CustomGlyphs;
+
+// Glyphs are drawn as SVG paths in an 8x8 box that is stretched over the cell. Eighths are used
+// because the block elements are all defined in terms of eighths of a cell.
+
+/// The weight of an arm of a box-drawing character.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Weight {
+    None,
+    Light,
+    Heavy,
+}
+
+use Weight::{Heavy as H, Light as L, None as N};
+
+/// The arms (up, right, down, left) of the box-drawing characters from U+2500 to U+254B. The
+/// dashed lines (U+2504 to U+250B) are left to the font.
+#[rustfmt::skip]
+const BOX_ARMS: [Option<[Weight; 4]>; 76] = [
+    // ─ ━ │ ┃
+    Some([N, L, N, L]), Some([N, H, N, H]), Some([L, N, L, N]), Some([H, N, H, N]),
+    // ┄ ┅ ┆ ┇ ┈ ┉ ┊ ┋
+    None, None, None, None, None, None, None, None,
+    // ┌ ┍ ┎ ┏
+    Some([N, L, L, N]), Some([N, H, L, N]), Some([N, L, H, N]), Some([N, H, H, N]),
+    // ┐ ┑ ┒ ┓
+    Some([N, N, L, L]), Some([N, N, L, H]), Some([N, N, H, L]), Some([N, N, H, H]),
+    // └ ┕ ┖ ┗
+    Some([L, L, N, N]), Some([L, H, N, N]), Some([H, L, N, N]), Some([H, H, N, N]),
+    // ┘ ┙ ┚ ┛
+    Some([L, N, N, L]), Some([L, N, N, H]), Some([H, N, N, L]), Some([H, N, N, H]),
+    // ├ ┝ ┞ ┟ ┠ ┡ ┢ ┣
+    Some([L, L, L, N]), Some([L, H, L, N]), Some([H, L, L, N]), Some([L, L, H, N]),
+    Some([H, L, H, N]), Some([H, H, L, N]), Some([L, H, H, N]), Some([H, H, H, N]),
+    // ┤ ┥ ┦ ┧ ┨ ┩ ┪ ┫
+    Some([L, N, L, L]), Some([L, N, L, H]), Some([H, N, L, L]), Some([L, N, H, L]),
+    Some([H, N, H, L]), Some([H, N, L, H]), Some([L, N, H, H]), Some([H, N, H, H]),
+    // ┬ ┭ ┮ ┯ ┰ ┱ ┲ ┳
+    Some([N, L, L, L]), Some([N, L, L, H]), Some([N, H, L, L]), Some([N, H, L, H]),
+    Some([N, L, H, L]), Some([N, L, H, H]), Some([N, H, H, L]), Some([N, H, H, H]),
+    // ┴ ┵ ┶ ┷ ┸ ┹ ┺ ┻
+    Some([L, L, N, L]), Some([L, L, N, H]), Some([L, H, N, L]), Some([L, H, N, H]),
+    Some([H, L, N, L]), Some([H, L, N, H]), Some([H, H, N, L]), Some([H, H, N, H]),
+    // ┼ ┽ ┾ ┿ ╀ ╁ ╂ ╃
+    Some([L, L, L, L]), Some([L, L, L, H]), Some([L, H, L, L]), Some([L, H, L, H]),
+    Some([H, L, L, L]), Some([L, L, H, L]), Some([H, L, H, L]), Some([H, L, L, H]),
+    // ╄ ╅ ╆ ╇ ╈ ╉ ╊ ╋
+    Some([H, H, L, L]), Some([L, L, H, H]), Some([L, H, H, L]), Some([H, H, L, H]),
+    Some([L, H, H, H]), Some([H, L, H, H]), Some([H, H, H, L]), Some([H, H, H, H]),
+];
+
+/// The arms (up, right, down, left) of the half lines from U+2574 to U+257F.
+#[rustfmt::skip]
+const HALF_ARMS: [[Weight; 4]; 12] = [
+    // ╴ ╵ ╶ ╷
+    [N, N, N, L], [L, N, N, N], [N, L, N, N], [N, N, L, N],
+    // ╸ ╹ ╺ ╻
+    [N, N, N, H], [H, N, N, N], [N, H, N, N], [N, N, H, N],
+    // ╼ ╽ ╾ ╿
+    [N, H, N, L], [L, N, H, N], [N, L, N, H], [H, N, L, N],
+];
+
+/// The paths of an arm (up, right, down, left), from the center of the cell to its edge.
+const ARM_PATHS: [&str; 4] = ["M4 0V4", "M4 4H8", "M4 4V8", "M0 4H4"];
+
+/// The paths of the double-line characters from U+2550 to U+256C. Each line is offset 1.5 units
+/// from the center of the cell. The characters that mix single and double lines are left to the
+/// font.
+const DOUBLE_PATHS: [(char, &str); 11] = [
+    ('═', "M0 2.5H8M0 5.5H8"),
+    ('║', "M2.5 0V8M5.5 0V8"),
+    ('╔', "M8 2.5H2.5V8M8 5.5H5.5V8"),
+    ('╗', "M0 2.5H5.5V8M0 5.5H2.5V8"),
+    ('╚', "M2.5 0V5.5H8M5.5 0V2.5H8"),
+    ('╝', "M5.5 0V5.5H0M2.5 0V2.5H0"),
+    ('╠', "M2.5 0V8M5.5 0V2.5H8M5.5 8V5.5H8"),
+    ('╣', "M5.5 0V8M2.5 0V2.5H0M2.5 8V5.5H0"),
+    ('╦', "M0 2.5H8M0 5.5H2.5V8M8 5.5H5.5V8"),
+    ('╩', "M0 5.5H8M0 2.5H2.5V0M8 2.5H5.5V0"),
+    ('╬', "M0 2.5H2.5V0M5.5 0V2.5H8M0 5.5H2.5V8M5.5 8V5.5H8"),
+];
+
+/// The paths of the rounded corners from U+256D to U+2570.
+const ROUNDED_PATHS: [&str; 4] = [
+    // ╭ ╮ ╯ ╰
+    "M8 4A4 4 0 0 0 4 8",
+    "M0 4A4 4 0 0 1 4 8",
+    "M4 0A4 4 0 0 1 0 4",
+    "M4 0A4 4 0 0 0 8 4",
+];
+
+/// The quadrants of a cell: upper left, upper right, lower left, lower right.
+const QUADRANTS: [&str; 4] = ["M0 0H4V4H0Z", "M4 0H8V4H4Z", "M0 4H4V8H0Z", "M4 4H8V8H4Z"];
+
+/// The quadrants (as indices into [`QUADRANTS`]) that are filled by the characters from U+2596 to
+/// U+259F.
+const QUADRANT_CHARS: [&[usize]; 10] = [
+    // ▖ ▗ ▘ ▙ ▚
+    &[2],
+    &[3],
+    &[0],
+    &[0, 2, 3],
+    &[0, 3],
+    // ▛ ▜ ▝ ▞ ▟
+    &[0, 1, 2],
+    &[0, 1, 3],
+    &[1],
+    &[1, 2],
+    &[1, 2, 3],
+];
+
+/// The column and row of each of the eight braille dots, ordered by the bit that represents it.
+const BRAILLE_DOTS: [(u32, u32); 8] = [
+    (0, 0),
+    (0, 1),
+    (0, 2),
+    (1, 0),
+    (1, 1),
+    (1, 2),
+    (0, 3),
+    (1, 3),
+];
+
+/// A single path that makes up a glyph.
+enum Stroke {
+    /// A line with the given weight.
+    Line(Weight, Cow<'static, str>),
+    /// A filled area with the given opacity.
+    Fill(f64, Cow<'static, str>),
+}
+
+/// Returns the paths needed to draw the given character, if the backend can draw it.
+fn glyph_paths(c: char, glyphs: CustomGlyphs) -> Option<Vec<Stroke>> {
+    let cp = c as u32;
+    match cp {
+        0x2500..=0x254B if glyphs.box_drawing => {
+            BOX_ARMS[(cp - 0x2500) as usize].map(|arms| arm_strokes(&arms))
+        }
+        0x2574..=0x257F if glyphs.box_drawing => {
+            Some(arm_strokes(&HALF_ARMS[(cp - 0x2574) as usize]))
+        }
+        0x2550..=0x256C if glyphs.box_drawing => DOUBLE_PATHS
+            .iter()
+            .find(|(ch, _)| *ch == c)
+            .map(|(_, d)| vec![Stroke::Line(Weight::Light, (*d).into())]),
+        0x256D..=0x2570 if glyphs.box_drawing => Some(vec![Stroke::Line(
+            Weight::Light,
+            ROUNDED_PATHS[(cp - 0x256D) as usize].into(),
+        )]),
+        0x2580..=0x259F if glyphs.block_elements => Some(block_strokes(cp)),
+        0x2800..=0x28FF if glyphs.braille => Some(braille_strokes(cp)),
+        _ => None,
+    }
+}
+
+/// Converts the arms of a box-drawing character into paths.
+fn arm_strokes(arms: &[Weight; 4]) -> Vec<Stroke> {
+    [Weight::Light, Weight::Heavy]
+        .into_iter()
+        .filter_map(|weight| {
+            let d: String = arms
+                .iter()
+                .zip(ARM_PATHS)
+                .filter(|(w, _)| **w == weight)
+                .map(|(_, d)| d)
+                .collect();
+            (!d.is_empty()).then(|| Stroke::Line(weight, d.into()))
+        })
+        .collect()
+}
+
+/// Converts a block element (U+2580 to U+259F) into paths.
+fn block_strokes(cp: u32) -> Vec<Stroke> {
+    let fill = |d: String| vec![Stroke::Fill(1.0, d.into())];
+    match cp {
+        // ▀
+        0x2580 => fill("M0 0H8V4H0Z".into()),
+        // ▁ through █, the lower eighths
+        0x2581..=0x2588 => fill(format!("M0 {}H8V8H0Z", 0x2588 - cp)),
+        // ▉ through ▏, the left eighths
+        0x2589..=0x258F => fill(format!("M0 0H{}V8H0Z", 8 - (cp - 0x2588))),
+        // ▐
+        0x2590 => fill("M4 0H8V8H4Z".into()),
+        // ░ ▒ ▓
+        0x2591..=0x2593 => vec![Stroke::Fill(
+            (cp - 0x2590) as f64 * 0.25,
+            "M0 0H8V8H0Z".into(),
+        )],
+        // ▔
+        0x2594 => fill("M0 0H8V1H0Z".into()),
+        // ▕
+        0x2595 => fill("M7 0H8V8H7Z".into()),
+        // ▖ through ▟, the quadrants
+        _ => fill(
+            QUADRANT_CHARS[(cp - 0x2596) as usize]
+                .iter()
+                .map(|i| QUADRANTS[*i])
+                .collect(),
+        ),
+    }
+}
+
+/// Converts a braille pattern (U+2800 to U+28FF) into paths. The cell is split into a 2x4 grid and
+/// each raised dot fills the middle of its part of the grid.
+fn braille_strokes(cp: u32) -> Vec<Stroke> {
+    let bits = cp - 0x2800;
+    let d: String = BRAILLE_DOTS
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| bits & (1 << i) != 0)
+        .map(|(_, (col, row))| format!("M{} {}.5h2v1h-2Z", col * 4 + 1, row * 2))
+        .collect();
+    if d.is_empty() {
+        Vec::new()
+    } else {
+        vec![Stroke::Fill(1.0, d.into())]
+    }
+}
+
+/// Renders the given character as an SVG that exactly covers a cell of the given size. Returns
+/// `None` if the character should be left to the font.
+pub(crate) fn render_glyph(
+    c: char,
+    glyphs: CustomGlyphs,
+    (width, height): (f64, f64),
+) -> Option<Html> {
+    let paths = glyph_paths(c, glyphs)?;
+    let light = (width / 8.0).max(1.0);
+    let paths = paths.into_iter().map(|stroke| match stroke {
+        Stroke::Line(weight, d) => {
+            let stroke_width = match weight {
+                Weight::Heavy => light * 2.0,
+                _ => light,
+            };
+            html! {
+                <path d={ d } fill="none" stroke="currentColor" stroke-width={ stroke_width.to_string() }
+                    stroke-linecap="square" vector-effect="non-scaling-stroke" />
+            }
+        }
+        Stroke::Fill(opacity, d) => html! {
+            <path d={ d } fill="currentColor" fill-opacity={ opacity.to_string() }
+                shape-rendering="crispEdges" />
+        },
+    });
+    let style = format!(
+        "display: inline-block; width: {width}px; height: {height}px; vertical-align: top;"
+    );
+    Some(html! {
+        <span { style }>
+            <svg viewBox="0 0 8 8" preserveAspectRatio="none" width="100%" height="100%"
+                style="display: block;">
+                { for paths }
+            </svg>
+        </span>
+    })
+}
@@ -0,0 +1,417 @@
+use std::{cell::RefCell, rc::Rc};
+
+use web_sys::{
+    js_sys::Function,
+    wasm_bindgen::{prelude::Closure, JsCast, JsValue},
+    ClipboardEvent, CompositionEvent, Event, HtmlTextAreaElement, InputEvent, KeyboardEvent,
+};
+use yew::{html, Callback, Context, Html, NodeRef};
+
+use crate::{
+    backend::INPUT_CLASS, prelude::utils::zoom_motion, TerminalApp, WebTermMessage, WebTerminal,
+};
+
+/// The events that the terminal delivers to the app. These are modeled on the events of the same
+/// name in crossterm, but they are not identical (see [`Event`](crate::event_loop::Event)).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TermEvent {
+    /// A key was pressed. Committed text, such as the result of an IME composition, is delivered
+    /// as one key press per character.
+    Key(KeyEvent),
+    /// The mouse was used over the terminal.
+    Mouse(MouseEvent),
+    /// Text was pasted into the terminal. Like a bracketed paste, the text is delivered in one
+    /// piece rather than as a series of key presses.
+    Paste(String),
+    /// The page gained the focus.
+    FocusGained,
+    /// The page lost the focus, e.g. because the user switched to another window.
+    FocusLost,
+}
+
+/// A single key press.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyEvent {
+    /// The key that was pressed.
+    pub code: KeyCode,
+    /// The modifier keys that were held while the key was pressed.
+    pub modifiers: KeyModifiers,
+}
+
+/// The keys that the terminal can report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyCode {
+    /// A character key, including the space bar.
+    Char(char),
+    /// The enter key.
+    Enter,
+    /// The backspace key.
+    Backspace,
+    /// The delete key.
+    Delete,
+    /// The insert key.
+    Insert,
+    /// The escape key.
+    Esc,
+    /// The tab key.
+    Tab,
+    /// The tab key, while shift is held.
+    BackTab,
+    /// The left arrow key.
+    Left,
+    /// The right arrow key.
+    Right,
+    /// The up arrow key.
+    Up,
+    /// The down arrow key.
+    Down,
+    /// The home key.
+    Home,
+    /// The end key.
+    End,
+    /// The page up key.
+    PageUp,
+    /// The page down key.
+    PageDown,
+    /// One of the function keys, e.g. `F(1)` for F1.
+    F(u8),
+}
+
+bitflags::bitflags! {
+    /// The modifier keys that can be held during a key press. The flags have the same names and
+    /// values as crossterm's, so checks like `modifiers.contains(KeyModifiers::CONTROL)` carry
+    /// over unchanged.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct KeyModifiers: u8 {
+        /// Either shift key.
+        const SHIFT = 0b0000_0001;
+        /// Either control key.
+        const CONTROL = 0b0000_0010;
+        /// Either alt (or option) key.
+        const ALT = 0b0000_0100;
+        /// Either super key, i.e. the command or Windows key. Browsers call this the meta key.
+        const SUPER = 0b0000_1000;
+    }
+}
+
+/// A mouse action over one of the terminal's cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MouseEvent {
+    /// What the mouse did.
+    pub kind: MouseEventKind,
+    /// The column of the cell under the mouse.
+    pub column: u16,
+    /// The row of the cell under the mouse.
+    pub row: u16,
+    /// The modifier keys that were held during the action.
+    pub modifiers: KeyModifiers,
+}
+
+/// The actions that the mouse can take.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MouseEventKind {
+    /// A button was pressed.
+    Down(MouseButton),
+    /// A button was released.
+    Up(MouseButton),
+    /// The mouse was moved while a button was held.
+    Drag(MouseButton),
+    /// The mouse was moved while no button was held.
+    Moved,
+    /// The wheel was scrolled down.
+    ScrollDown,
+    /// The wheel was scrolled up.
+    ScrollUp,
+}
+
+/// The mouse buttons.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MouseButton {
+    /// The left (or primary) button.
+    Left,
+    /// The right (or secondary) button.
+    Right,
+    /// The middle button, which is often the wheel.
+    Middle,
+}
+
+impl MouseButton {
+    /// Converts the `button` property of a browser mouse event, which names the button that was
+    /// pressed or released.
+    pub(crate) fn from_button(button: i16) -> Option<Self> {
+        match button {
+            0 => Some(Self::Left),
+            1 => Some(Self::Middle),
+            2 => Some(Self::Right),
+            _ => None,
+        }
+    }
+
+    /// Converts the `buttons` property of a browser mouse event, which is a bitmask of the buttons
+    /// that are held. If several are held, the left button is preferred, then the right.
+    pub(crate) fn from_buttons(buttons: u16) -> Option<Self> {
+        [(1, Self::Left), (2, Self::Right), (4, Self::Middle)]
+            .into_iter()
+            .find_map(|(bit, button)| (buttons & bit != 0).then_some(button))
+    }
+}
+
+impl KeyModifiers {
+    /// No modifier keys were held.
+    pub const NONE: Self = Self::empty();
+
+    /// Combines the state of each of the modifier keys, in the order that browser events report
+    /// them.
+    fn from_keys(shift: bool, ctrl: bool, alt: bool, meta: bool) -> Self {
+        [
+            (shift, Self::SHIFT),
+            (ctrl, Self::CONTROL),
+            (alt, Self::ALT),
+            (meta, Self::SUPER),
+        ]
+        .into_iter()
+        .filter(|(held, _)| *held)
+        .fold(Self::NONE, |digest, (_, flag)| digest | flag)
+    }
+
+    /// Reads the modifier keys that were held during a browser mouse event.
+    pub(crate) fn from_mouse(event: &web_sys::MouseEvent) -> Self {
+        Self::from_keys(
+            event.shift_key(),
+            event.ctrl_key(),
+            event.alt_key(),
+            event.meta_key(),
+        )
+    }
+}
+
+impl KeyEvent {
+    /// Creates a key press without any modifiers.
+    pub fn new(code: KeyCode) -> Self {
+        Self {
+            code,
+            modifiers: KeyModifiers::default(),
+        }
+    }
+
+    /// Converts a browser `keydown` event into a key press. Returns `None` for keys that are not
+    /// reported (e.g. a lone modifier key) and for plain characters, which are read from the
+    /// committed text instead so that IMEs and soft keyboards work.
+    fn from_keyboard(event: &KeyboardEvent) -> Option<Self> {
+        let modifiers = KeyModifiers::from_keys(
+            event.shift_key(),
+            event.ctrl_key(),
+            event.alt_key(),
+            event.meta_key(),
+        );
+        let key = event.key();
+        let code = match key.as_str() {
+            "Enter" => KeyCode::Enter,
+            "Backspace" => KeyCode::Backspace,
+            "Delete" => KeyCode::Delete,
+            "Insert" => KeyCode::Insert,
+            "Escape" => KeyCode::Esc,
+            "Tab" if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+            "Tab" => KeyCode::Tab,
+            "ArrowLeft" => KeyCode::Left,
+            "ArrowRight" => KeyCode::Right,
+            "ArrowUp" => KeyCode::Up,
+            "ArrowDown" => KeyCode::Down,
+            "Home" => KeyCode::Home,
+            "End" => KeyCode::End,
+            "PageUp" => KeyCode::PageUp,
+            "PageDown" => KeyCode::PageDown,
+            _ => {
+                if let Some(n) = key.strip_prefix('F').and_then(|n| n.parse().ok()) {
+                    KeyCode::F(n)
+                } else {
+                    let mut chars = key.chars();
+                    let c = chars.next().filter(|_| chars.next().is_none())?;
+                    // Plain characters arrive through the "input" event. Shortcuts don't.
+                    let shortcut = KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SUPER;
+                    if !modifiers.intersects(shortcut) {
+                        return None;
+                    }
+                    KeyCode::Char(c)
+                }
+            }
+        };
+        Some(Self { code, modifiers })
+    }
+}
+
+/// Splits committed text into a series of key presses.
+fn text_to_keys(text: &str) -> impl '_ + Iterator<Item = TermEvent> {
+    text.chars().map(|c| {
+        let code = match c {
+            '\n' | '\r' => KeyCode::Enter,
+            '\t' => KeyCode::Tab,
+            c => KeyCode::Char(c),
+        };
+        TermEvent::Key(KeyEvent::new(code))
+    })
+}
+
+/// The text that the textarea holds between inputs. Soft keyboards (notably on Android) don't
+/// report Backspace as a key press and only delete text that exists, so the textarea always holds
+/// a character for them to delete.
+const SENTINEL: &str = " ";
+
+/// Renders the hidden `<textarea>` that receives the browser's keyboard, IME, and paste events.
+/// The textarea is reset to the sentinel after its text is forwarded, so the app is the only owner
+/// of the text being edited. If the zoom keybindings are enabled, those keys are left to the zoom
+/// handler rather than forwarded to the app.
+pub(crate) fn text_input<A: TerminalApp>(
+    ctx: &Context<WebTerminal<A>>,
+    node: NodeRef,
+    selected: Rc<RefCell<Option<String>>>,
+    zoom_keys: bool,
+) -> Html {
+    let cb = ctx.link().callback(WebTermMessage::Event);
+
+    let onkeydown = {
+        let cb = cb.clone();
+        move |event: KeyboardEvent| {
+            // Key presses that are part of an IME composition are handled by the IME
+            if event.is_composing() || event.key_code() == 229 {
+                return;
+            }
+            if zoom_keys && zoom_motion(&event).is_some() {
+                return;
+            }
+            if let Some(key) = KeyEvent::from_keyboard(&event) {
+                // Let the browser turn the paste shortcut into a "paste" event and, while there
+                // is a selection, the copy shortcut into a "copy" event
+                let shortcut = match key.code {
+                    KeyCode::Char('v' | 'V') => true,
+                    KeyCode::Char('c' | 'C') => selected.borrow().is_some(),
+                    _ => false,
+                } && key
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::SUPER);
+                if !shortcut {
+                    event.prevent_default();
+                    cb.emit(TermEvent::Key(key));
+                }
+            }
+        }
+    };
+
+    let oninput = {
+        let cb = cb.clone();
+        move |event: InputEvent| {
+            if event.is_composing() {
+                return;
+            }
+            match event.input_type().as_str() {
+                // Composed text is delivered when the composition ends
+                "insertCompositionText" | "insertFromComposition" => return,
+                "insertLineBreak" | "insertParagraph" => {
+                    cb.emit(TermEvent::Key(KeyEvent::new(KeyCode::Enter)))
+                }
+                // Soft keyboards often don't report which key was pressed
+                "deleteContentBackward" => {
+                    cb.emit(TermEvent::Key(KeyEvent::new(KeyCode::Backspace)))
+                }
+                "deleteContentForward" => cb.emit(TermEvent::Key(KeyEvent::new(KeyCode::Delete))),
+                _ => {
+                    if let Some(data) = event.data() {
+                        text_to_keys(&data).for_each(|e| cb.emit(e));
+                    }
+                }
+            }
+            clear_input(&event);
+        }
+    };
+
+    let onpaste = {
+        move |event: Event| {
+            let Ok(event) = event.dyn_into::<ClipboardEvent>() else {
+                return;
+            };
+            let text = event
+                .clipboard_data()
+                .and_then(|data| data.get_data("text/plain").ok());
+            if let Some(text) = text.filter(|t| !t.is_empty()) {
+                event.prevent_default();
+                cb.emit(TermEvent::Paste(text));
+            }
+        }
+    };
+
+    html! {
+        <textarea
+            ref={ node }
+            { onkeydown }
+            { oninput }
+            { onpaste }
+            autocapitalize="off"
+            autocomplete="off"
+            spellcheck="false"
+            aria-label="Terminal input"
+            value={ SENTINEL }
+            class={ INPUT_CLASS }
+        />
+    }
+}
+
+/// Yew does not expose the composition events, so the "compositionend" listener is attached to
+/// the textarea directly. This is where the text from an IME is committed.
+pub(crate) fn process_composition_end_event(cb: Callback<TermEvent>) -> Function {
+    let func = move |event: JsValue| {
+        let event: CompositionEvent = event.into();
+        if let Some(data) = event.data() {
+            text_to_keys(&data).for_each(|e| cb.emit(e));
+        }
+        clear_input(&event);
+    };
+    Closure::<dyn 'static + Fn(JsValue)>::new(func)
+        .into_js_value()
+        .into()
+}
+
+/// Resets the textarea that the event was sent to.
+fn clear_input(event: &Event) {
+    if let Some(area) = event
+        .target()
+        .and_then(|t| t.dyn_into::<HtmlTextAreaElement>().ok())
+    {
+        reset_input(&area);
+    }
+}
+
+/// Resets the textarea to the sentinel, with the caret after it.
+pub(crate) fn reset_input(area: &HtmlTextAreaElement) {
+    area.set_value(SENTINEL);
+    let end = SENTINEL.len() as u32;
+    let _ = area.set_selection_range(end, end);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn combines_modifier_keys() {
+        assert_eq!(
+            KeyModifiers::from_keys(false, false, false, false),
+            KeyModifiers::NONE
+        );
+        let modifiers = KeyModifiers::from_keys(true, false, true, false);
+        assert_eq!(modifiers, KeyModifiers::SHIFT | KeyModifiers::ALT);
+        assert!(modifiers.contains(KeyModifiers::SHIFT));
+        assert!(!modifiers.contains(KeyModifiers::CONTROL));
+        assert_eq!(
+            KeyModifiers::from_keys(false, true, false, true),
+            KeyModifiers::CONTROL | KeyModifiers::SUPER
+        );
+    }
+
+    #[test]
+    fn matches_crossterm_flag_values() {
+        assert_eq!(KeyModifiers::SHIFT.bits(), 0b0001);
+        assert_eq!(KeyModifiers::CONTROL.bits(), 0b0010);
+        assert_eq!(KeyModifiers::ALT.bits(), 0b0100);
+        assert_eq!(KeyModifiers::SUPER.bits(), 0b1000);
+    }
+}
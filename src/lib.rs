@@ -4,16 +4,16 @@
 //! in the broswer with the help of [Yew](https://crates.io/crates/yew). Currently, this crate is
 //! centered around transforming the text-based displays that ratatui generates into HTML
 //! DOM-elements. Some interactivity is supported, like hyperlinks, on-click callbacks, and
-//! scrolling (both on mobile devices and with a mouse); however, webatui is not a fully
-//! interactive terminal yet. Many things, such as cursor location, editing text, etc, are not
-//! supported. Other things, like getting keyboard inputs, are possible via the
-//! [web-sys](https://crates.io/crates/web-sys) crate, but there is no direct ability to setup and
-//! configure this, yet.
+//! scrolling (both on mobile devices and with a mouse). Keyboard input, including IMEs and soft
+//! keyboards on mobile, can be enabled via [`WebTermProps::with_text_input`], in which case the
+//! cursor is rendered wherever the app places it. Webatui is not a fully interactive terminal yet,
+//! but other things are possible via the [web-sys](https://crates.io/crates/web-sys) crate.
 //!
 //! Many of the web-specific details have been abstracted away so that porting existing apps
 //! is as easy as possible. To get started, create a struct that will hold your app's logic,
 //! implement the [`TerminalApp`] trait for it, and run the [`run_tui`] function with an instance
-//! of your app.
+//! of your app (or, if your app isn't `Clone`, the [`run_tui_with`] function with a function that
+//! creates your app).
 //! ```no_run
 //! use ratatui::{prelude::*, widgets::*};
 //! use webatui::prelude::*;
@@ -53,6 +53,9 @@
 //! necessary spans have been hydrated, those spans are composed into a series of HTML `<pre>` tags
 //! and rendered into the DOM.
 //!
+//! Renders are batched so that the terminal is rendered at most once per animation frame, no matter
+//! how many updates ask for one. [`TermContext::force_redraw`] skips this wait when needed.
+//!
 //! A note about hydration: When a widget is rendered, the [`YewBackend`] gets it
 //! character-by-character. This limits the backend's ability to create blocks that need hydrated.
 //! So, a multi-line widget will be split into a series of dehydrated spans that will be hydrated
@@ -73,27 +76,91 @@
     clippy::all
 )]
 
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, future::Future, rc::Rc, time::Duration};
 
-use backend::{DehydratedSpan, YewBackend};
-use base16_palettes::Palette;
+use backend::{CustomGlyphs, DehydratedSpan, StyleMode, ViewportMode, YewBackend, NO_SELECT_CLASS};
+use config::TermConfig;
+use embed::EmbedRegion;
+use futures::{FutureExt, Stream, StreamExt};
+use input::{
+    process_composition_end_event, reset_input, text_input, KeyModifiers, MouseButton,
+    MouseEventKind, TermEvent,
+};
+use palette::{AdaptivePalette, TermPalette};
 use prelude::utils::{
-    process_resize_event, process_touch_init_event, process_touch_move_event, process_wheel_event,
-    TouchScroll,
+    get_color_scheme_query, get_visibility, process_color_scheme_event, process_copy_event,
+    process_focus_event, process_paste_event, process_resize_event, process_touch_init_event,
+    process_touch_move_event, process_visibility_event, process_wheel_event,
+    process_zoom_key_event, watch_pixel_ratio, write_clipboard, TouchScroll,
 };
-use ratatui::{prelude::Rect, Frame, Terminal};
-use yew::{Component, Context, Properties};
+use ratatui::{buffer::Buffer, prelude::Rect, Frame, Terminal, TerminalOptions, Viewport};
+use selection::{Selection, SelectionMode, SelectionMotion};
+use tasks::TaskSet;
+use ticker::{FrameScheduler, Ticker};
+use web_sys::{js_sys::Function, HtmlTextAreaElement, MediaQueryList, MouseEvent};
+use yew::{html, Callback, Component, Context, NodeRef, Properties};
 
 /// Contains the terminal backend that transforms the text rendered from ratatui widgets into HTML.
 pub mod backend;
+#[cfg(any(feature = "ratatui_0_28", feature = "ratatui_0_29"))]
+mod compat;
+/// Contains the configuration for the terminal's font settings.
+pub mod config;
+/// Contains the tools for placing content, such as images and other HTML, over regions of the
+/// terminal.
+pub mod embed;
+/// Contains the adapter for apps that are written as an event loop, like most crossterm-based
+/// apps.
+pub mod event_loop;
+mod glyphs;
+/// Contains the input events that the terminal forwards to the app, such as key presses.
+pub mod input;
+/// Contains the palettes that the backend uses to render indexed colors, including user-defined
+/// palettes.
+pub mod palette;
 /// Common includes needed when working with this crate.
 pub mod prelude;
+/// Contains the terminal-style text selection, which selects cells rather than HTML.
+pub mod selection;
+mod tasks;
+mod ticker;
 mod utils;
 
 /// A container for a TUI app that renders to HTML.
 pub struct WebTerminal<A> {
     app: A,
     term: RefCell<Terminal<YewBackend>>,
+    /// The `prefers-color-scheme` query. This is held onto so that its `onchange` callback lives
+    /// as long as the terminal.
+    scheme_query: Option<MediaQueryList>,
+    /// The `resolution` query that tracks the current device pixel ratio. This query is replaced
+    /// every time the ratio changes.
+    ratio_query: Rc<RefCell<Option<MediaQueryList>>>,
+    /// The "keydown" listener that handles the zoom keybindings, if they are enabled.
+    zoom_keys: Option<Function>,
+    /// The "paste" listener that forwards pastes to the app.
+    paste: Option<Function>,
+    /// The "copy" listener that copies the selected cells, if selection is enabled.
+    copy: Option<Function>,
+    /// The "focus" and "blur" listeners that tell the app when the page gains or loses the focus.
+    focus: Option<(Function, Function)>,
+    /// The "visibilitychange" listener that tells the app when the page is hidden or shown.
+    visibility: Option<Function>,
+    /// The text of the selected cells. This is shared with the "copy" listener, which has to set
+    /// the copied text before the event returns.
+    selected: Rc<RefCell<Option<String>>>,
+    /// Drives the app's ticks, if the app has a tick rate.
+    ticker: Option<Ticker>,
+    /// The futures that the app has spawned.
+    tasks: TaskSet,
+    /// Batches the renders requested by updates into one per animation frame.
+    frames: Option<FrameScheduler>,
+    /// Whether the app has asked to skip the batching for the current update.
+    force_redraw: bool,
+    /// The hidden `<textarea>` that receives text input, if text input is enabled.
+    text_input: Option<NodeRef>,
+    /// The number of lines that had been pushed into the backend's history as of the last render.
+    history_pushed: usize,
 }
 
 /// The message type generated by callbacks and sent to the [`WebTerminal`].
@@ -101,10 +168,31 @@ pub enum WebTermMessage<M> {
     /// Contains a message that will be processed by the inner [`TerminalApp`] that is held by the
     /// `WebTerminal`.
     Inner(M),
+    /// Contains several messages for the inner [`TerminalApp`]. These are processed together, so
+    /// they cause at most one render.
+    Batch(Vec<M>),
     /// The browser window has changed size.
     Resized,
     /// The user as scrolled one unit.
     Scrolled(ScrollMotion),
+    /// The OS's light/dark preference has changed.
+    ColorSchemeChanged(ColorScheme),
+    /// Changes the terminal's font scale.
+    Zoom(ZoomMotion),
+    /// The user has typed or pasted into the terminal.
+    Event(TermEvent),
+    /// The user has changed their selection.
+    Select(SelectionMotion),
+    /// The user has used the mouse over the terminal. The position is the client coordinates (in
+    /// pixels) of the mouse, which the backend converts into a cell.
+    Mouse(MouseEventKind, i32, i32, KeyModifiers),
+    /// The page has been hidden or shown, e.g. by switching tabs.
+    VisibilityChanged(Visibility),
+    /// The app's tick rate has elapsed.
+    Tick,
+    /// Renders the terminal. Updates that need a render request one of these for the next
+    /// animation frame.
+    Redraw,
 }
 
 /// The direction that a user has scrolled
@@ -116,6 +204,39 @@ pub enum ScrollMotion {
     Down,
 }
 
+/// The ways that the terminal's font scale can be changed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ZoomMotion {
+    /// Makes the text one step larger.
+    In,
+    /// Makes the text one step smaller.
+    Out,
+    /// Sets the font scale back to `1.0`.
+    Reset,
+    /// Sets the font scale to the given value.
+    To(f64),
+}
+
+/// Whether the page is visible to the user, as reported by the document's `visibilityState`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visibility {
+    /// At least part of the page is visible.
+    Visible,
+    /// The page is not visible, e.g. because it is in a background tab or the window is
+    /// minimized.
+    Hidden,
+}
+
+/// The color scheme preferred by the user's OS, as reported by the `prefers-color-scheme` media
+/// query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorScheme {
+    /// The user prefers light themes.
+    Light,
+    /// The user prefers dark themes.
+    Dark,
+}
+
 impl<M> WebTermMessage<M> {
     /// Creates a
     pub fn new<I: Into<M>>(inner: I) -> Self {
@@ -130,24 +251,166 @@ impl<M> From<M> for WebTermMessage<M> {
 }
 
 /// In the public API because of the component impl of WebTerminal
-#[derive(Properties, PartialEq)]
-pub struct WebTermProps<M: PartialEq> {
-    inner: M,
-    palette: Palette,
+#[derive(Properties)]
+pub struct WebTermProps<A> {
+    inner: AppFactory<A>,
+    palette: TermPalette,
+    adaptive: Option<AdaptivePalette>,
+    style_nonce: Option<String>,
+    style_mode: StyleMode,
+    custom_glyphs: CustomGlyphs,
+    config: TermConfig,
+    zoom_keys: bool,
+    text_input: bool,
+    selection: bool,
+    mouse_capture: bool,
+    viewport: ViewportMode,
+    scrollback: Option<usize>,
 }
 
-impl<M: PartialEq> WebTermProps<M> {
+/// Creates the app when the terminal is created. This lets apps that aren't `Clone` be passed via
+/// [`Properties`]. The factory is only used when the terminal is created, so it is ignored when
+/// props are compared.
+struct AppFactory<A>(Rc<dyn Fn() -> A>);
+
+impl<A: 'static + Clone> WebTermProps<A> {
     /// A constructor for the `WebTermProps` that uses the default color pallete.
-    pub fn new(inner: M) -> Self {
+    pub fn new(inner: A) -> Self {
+        Self::new_with_factory(move || inner.clone())
+    }
+
+    /// A constructor for the `WebTermProps` that uses the given color pallete. This can either be
+    /// a [`Palette`](base16_palettes::Palette) preset or a [`CustomPalette`](palette::CustomPalette).
+    pub fn new_with_palette<P: Into<TermPalette>>(inner: A, palette: P) -> Self {
+        Self::new(inner).with_palette(palette)
+    }
+
+    /// A constructor for the `WebTermProps` that follows the OS's light/dark preference. The
+    /// terminal will switch between the given palettes whenever that preference changes.
+    pub fn new_with_adaptive_palette(inner: A, palette: AdaptivePalette) -> Self {
+        Self::new(inner).with_adaptive_palette(palette)
+    }
+}
+
+impl<A> WebTermProps<A> {
+    /// A constructor for the `WebTermProps` that creates the app by calling the given function
+    /// when the terminal is created. Unlike the other constructors, this does not require the app
+    /// to be `Clone`, so the app can hold things like channels and other unique handles.
+    ///
+    /// The app is created once, so passing props with a different app (or factory) to an existing
+    /// terminal does not replace its app. Give the terminal a new `key` to re-create it.
+    pub fn new_with_factory<F: 'static + Fn() -> A>(factory: F) -> Self {
         Self {
-            inner,
-            palette: Palette::default(),
+            inner: AppFactory(Rc::new(factory)),
+            palette: TermPalette::default(),
+            adaptive: None,
+            style_nonce: None,
+            style_mode: StyleMode::default(),
+            custom_glyphs: CustomGlyphs::default(),
+            config: TermConfig::default(),
+            zoom_keys: false,
+            text_input: false,
+            selection: false,
+            mouse_capture: false,
+            viewport: ViewportMode::default(),
+            scrollback: None,
         }
     }
 
-    /// A constructor for the `WebTermProps` that uses the given color pallete
-    pub fn new_with_palette(inner: M, palette: Palette) -> Self {
-        Self { inner, palette }
+    /// Sets the color palette. This can either be a [`Palette`](base16_palettes::Palette) preset
+    /// or a [`CustomPalette`](palette::CustomPalette).
+    pub fn with_palette<P: Into<TermPalette>>(mut self, palette: P) -> Self {
+        self.palette = palette.into();
+        self.adaptive = None;
+        self
+    }
+
+    /// Sets the pair of palettes that the terminal switches between as the OS's light/dark
+    /// preference changes.
+    pub fn with_adaptive_palette(mut self, palette: AdaptivePalette) -> Self {
+        self.palette = palette.dark.clone();
+        self.adaptive = Some(palette);
+        self
+    }
+
+    /// Sets the nonce that is given to the `<style>` element that the terminal injects into the
+    /// page. This is needed if the page's Content Security Policy restricts styles via nonces.
+    /// Pair this with [`StyleMode::Classes`]; see [`YewBackend::set_style_nonce`] for the elements
+    /// that still need `'unsafe-inline'`.
+    pub fn with_style_nonce(mut self, nonce: String) -> Self {
+        self.style_nonce = Some(nonce);
+        self
+    }
+
+    /// Sets how the terminal attaches styles to the spans that it renders. See [`StyleMode`] for
+    /// details.
+    pub fn with_style_mode(mut self, mode: StyleMode) -> Self {
+        self.style_mode = mode;
+        self
+    }
+
+    /// Sets which groups of characters (e.g. box-drawing characters) the terminal draws itself
+    /// rather than leaving them to the font. See [`CustomGlyphs`] for details.
+    pub fn with_custom_glyphs(mut self, glyphs: CustomGlyphs) -> Self {
+        self.custom_glyphs = glyphs;
+        self
+    }
+
+    /// Sets the font settings that the terminal uses to render text and to calculate the size of
+    /// the character grid.
+    pub fn with_config(mut self, config: TermConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Enables the zoom keybindings. When enabled, `Ctrl` + `=`/`+` zooms in, `Ctrl` + `-` zooms
+    /// out, and `Ctrl` + `0` resets the zoom. These replace the browser's own zoom bindings.
+    pub fn with_zoom_keys(mut self, enabled: bool) -> Self {
+        self.zoom_keys = enabled;
+        self
+    }
+
+    /// Enables text input. When enabled, the terminal holds a hidden `<textarea>` that follows the
+    /// cursor. It receives the keyboard (including soft keyboards on mobile), IME compositions,
+    /// and pastes, which are forwarded to the app via [`TerminalApp::event`]. Clicking the
+    /// terminal focuses it.
+    pub fn with_text_input(mut self, enabled: bool) -> Self {
+        self.text_input = enabled;
+        self
+    }
+
+    /// Enables terminal-style selection. When enabled, dragging the mouse selects cells rather
+    /// than the rendered HTML, and holding `Alt` while dragging selects a rectangle of cells (see
+    /// [`SelectionMode`]). Copying then yields the selected text, minus the regions returned by
+    /// [`TerminalApp::unselectable`].
+    pub fn with_selection(mut self, enabled: bool) -> Self {
+        self.selection = enabled;
+        self
+    }
+
+    /// Enables mouse capture. When enabled, presses, releases, and movements of the mouse over the
+    /// terminal are forwarded to the app via [`TerminalApp::event`], along with the cell under the
+    /// mouse.
+    pub fn with_mouse_capture(mut self, enabled: bool) -> Self {
+        self.mouse_capture = enabled;
+        self
+    }
+
+    /// Sets how the terminal is sized. By default, the terminal fills the window. A fixed-size
+    /// terminal has exactly the given number of columns and rows, while an inline terminal grows
+    /// the page as the app inserts lines above it via `Terminal::insert_before` (accessible via
+    /// [`TermContext::terminal`]).
+    pub fn with_viewport(mut self, viewport: ViewportMode) -> Self {
+        self.viewport = viewport;
+        self
+    }
+
+    /// Enables scrollback, keeping at most `max_lines` lines that have left the terminal (via
+    /// [`TermContext::insert_before`]) as HTML above it. The page is then scrolled natively, so
+    /// the wheel and touch gestures are no longer passed to [`TerminalApp::scroll`].
+    pub fn with_scrollback(mut self, max_lines: usize) -> Self {
+        self.scrollback = Some(max_lines);
+        self
     }
 }
 
@@ -155,6 +418,8 @@ impl<M: PartialEq> WebTermProps<M> {
 pub struct TermContext<'a, A: TerminalApp> {
     ctx: &'a Context<WebTerminal<A>>,
     term: &'a mut Terminal<YewBackend>,
+    tasks: &'a mut TaskSet,
+    force_redraw: &'a mut bool,
 }
 
 impl<'a, A: TerminalApp> TermContext<'a, A> {
@@ -168,14 +433,86 @@ impl<'a, A: TerminalApp> TermContext<'a, A> {
     pub fn ctx(&self) -> &Context<WebTerminal<A>> {
         self.ctx
     }
+
+    /// Runs the future on the browser's event loop and passes its output to the app as a
+    /// message once it completes. This is useful for things like fetching data over HTTP. Any
+    /// futures that are still running when the terminal is destroyed are cancelled.
+    pub fn spawn<F>(&mut self, future: F)
+    where
+        F: 'static + Future<Output = A::Message>,
+    {
+        let cb = self.ctx.link().callback(WebTermMessage::Inner);
+        self.tasks.spawn(async move { cb.emit(future.await) });
+    }
+
+    /// Passes each item of the stream to the app as a message, e.g. the messages received over a
+    /// WebSocket or from an `EventSource`. Items that arrive together are delivered as one batch,
+    /// so a burst of messages causes one render rather than one per message. The subscription is
+    /// torn down when the terminal is destroyed.
+    pub fn subscribe<S>(&mut self, stream: S)
+    where
+        S: 'static + Stream<Item = A::Message>,
+    {
+        let cb = self.ctx.link().callback(WebTermMessage::Batch);
+        self.tasks.spawn(async move {
+            let mut stream = Box::pin(stream.fuse());
+            while let Some(item) = stream.next().await {
+                let mut batch = vec![item];
+                // Take every item that is ready now. The stream is polled again (and can register
+                // to be woken) by the outer loop.
+                while let Some(Some(item)) = stream.next().now_or_never() {
+                    batch.push(item);
+                }
+                cb.emit(batch);
+            }
+        });
+    }
+
+    /// Renders the terminal as soon as the current update finishes. Normally, renders are batched
+    /// so that the terminal renders at most once per animation frame, which keeps bursts of
+    /// messages cheap. This skips that wait, e.g. to echo a key press without any delay.
+    pub fn force_redraw(&mut self) {
+        *self.force_redraw = true;
+    }
+
+    /// Returns whether the page is currently visible to the user.
+    pub fn visibility(&self) -> Visibility {
+        get_visibility()
+    }
+
+    /// Inserts `height` lines above the terminal, which are drawn by the given function. This is
+    /// how an inline terminal (see [`WebTermProps::with_viewport`]) or one with scrollback (see
+    /// [`WebTermProps::with_scrollback`]) adds to its history, like `Terminal::insert_before`. The
+    /// lines are dropped if the terminal doesn't keep a history.
+    pub fn insert_before<F>(&mut self, height: u16, draw_fn: F)
+    where
+        F: FnOnce(&mut Buffer),
+    {
+        if matches!(self.term.backend().viewport(), ViewportMode::Inline(_)) {
+            let _ = self.term.insert_before(height, draw_fn);
+            return;
+        }
+        let width = self.term.size().map(|area| area.width).unwrap_or_default();
+        let mut buffer = Buffer::empty(Rect::new(0, 0, width, height));
+        draw_fn(&mut buffer);
+        self.term.backend_mut().push_history(&buffer);
+    }
+
+    /// Writes the given text to the system clipboard. The write happens asynchronously. Returns
+    /// `false` if the browser does not expose the clipboard, which is the case for pages that are
+    /// not served over HTTPS (or from `localhost`).
+    pub fn copy_to_clipboard(&self, text: &str) -> bool {
+        write_clipboard(text)
+    }
 }
 
 /// The core user-facing abstraction of this crate. A terminal app is a type that can be wrapped by
 /// a [`WebTerminal`] and be displayed by Yew.
 ///
-/// Because the app needs to be passed via [`Properties`], it needs to be `'static`, `Clone`, and
-/// `PartialEq`.
-pub trait TerminalApp: 'static + Clone + PartialEq {
+/// Because the app is created by the [`WebTerminal`] component, it needs to be `'static`. Apps
+/// that are `Clone` can be passed to [`run_tui`] directly. Other apps can be created by a
+/// function that is passed to [`run_tui_with`].
+pub trait TerminalApp: 'static + Sized {
     /// The message type that this type uses to update.
     type Message;
 
@@ -184,7 +521,11 @@ pub trait TerminalApp: 'static + Clone + PartialEq {
     #[allow(unused_variables)]
     fn setup(&mut self, ctx: &Context<WebTerminal<Self>>) {}
 
-    // TODO: Add optional resize method
+    /// Notifies the app that the size of the character grid has changed, either because the
+    /// window was resized or because the font scale or pixel ratio changed. The terminal is always
+    /// re-rendered afterwards.
+    #[allow(unused_variables)]
+    fn resize(&mut self, area: Rect) {}
 
     /// Allows the app to initialize its environment, such as setting up callbacks to window
     /// events.
@@ -193,7 +534,27 @@ pub trait TerminalApp: 'static + Clone + PartialEq {
         false
     }
 
-    /// Updates the app with a message.
+    /// Notifies the app that the OS's light/dark preference has changed and that the terminal has
+    /// switched palettes accordingly. This is only called if the [`WebTerminal`] was created with
+    /// an [`AdaptivePalette`]. The terminal is always re-rendered afterwards.
+    ///
+    /// This is also called once while the terminal is created (after [`TerminalApp::setup`]) with
+    /// the preference at that time, so the app's styling matches the OS from the first frame.
+    #[allow(unused_variables)]
+    fn color_scheme_changed(&mut self, scheme: ColorScheme) {}
+
+    /// Delivers a key press, mouse action, paste, or focus change to the app and returns whether
+    /// the terminal needs to be re-rendered. Pastes and focus changes are always delivered, but key
+    /// presses and mouse actions are only generated if the [`WebTerminal`] was created with text
+    /// input and mouse capture enabled, respectively. The cursor is drawn wherever the app
+    /// places it via [`Frame::set_cursor`].
+    #[allow(unused_variables)]
+    fn event(&mut self, event: TermEvent) -> bool {
+        false
+    }
+
+    /// Updates the app with a message and returns whether the terminal needs to be re-rendered.
+    /// The render happens on the next animation frame, along with any other updates' renders.
     fn update(&mut self, ctx: TermContext<'_, Self>, msg: Self::Message) -> bool;
 
     /// Takes a Ratatui [`Frame`] and renders widgets onto it.
@@ -203,6 +564,43 @@ pub trait TerminalApp: 'static + Clone + PartialEq {
     /// hyperlinks, etc.
     #[allow(unused_variables)]
     fn hydrate(&self, ctx: &Context<WebTerminal<Self>>, span: &mut DehydratedSpan) {}
+
+    /// Notifies the app that the page has been hidden or shown and returns whether the terminal
+    /// needs to be re-rendered. Apps can use this to pause animations while hidden and to refresh
+    /// stale data once shown again.
+    #[allow(unused_variables)]
+    fn visibility_changed(&mut self, visibility: Visibility) -> bool {
+        false
+    }
+
+    /// Returns how often the app should be ticked via [`TerminalApp::on_tick`]. Returning `None`
+    /// (the default) stops the ticks. This is checked after every update, so apps can start and
+    /// stop ticking as needed, e.g. only while a spinner is visible.
+    ///
+    /// Ticks are aligned to the browser's animation frames, so there is at most one tick per frame
+    /// and ticks pause while the page is hidden.
+    fn tick_rate(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Ticks the app and returns whether the terminal needs to be re-rendered. This is useful for
+    /// animations, like spinners and live charts.
+    fn on_tick(&mut self) -> bool {
+        false
+    }
+
+    /// Returns the regions of the terminal that are excluded from selections, such as borders and
+    /// line numbers. This is only called if the [`WebTerminal`] was created with selection
+    /// enabled.
+    #[allow(unused_variables)]
+    fn unselectable(&self, area: Rect) -> Vec<Rect> {
+        Vec::new()
+    }
+
+    /// Takes a region of the terminal that was marked with the [`EMBED`](crate::embed::EMBED)
+    /// modifier and allows the app to place content, such as an image or any other HTML, over it.
+    #[allow(unused_variables)]
+    fn embed(&self, ctx: &Context<WebTerminal<Self>>, region: &mut EmbedRegion) {}
 }
 
 impl<A: Default + TerminalApp> WebTerminal<A> {
@@ -218,72 +616,485 @@ impl<A: Default> Default for WebTerminal<A> {
         Self {
             app: A::default(),
             term: RefCell::new(Terminal::new(YewBackend::new()).unwrap()),
+            scheme_query: None,
+            ratio_query: Rc::default(),
+            zoom_keys: None,
+            paste: None,
+            copy: None,
+            focus: None,
+            visibility: None,
+            selected: Rc::default(),
+            ticker: None,
+            tasks: TaskSet::default(),
+            frames: None,
+            force_redraw: false,
+            text_input: None,
+            history_pushed: 0,
         }
     }
 }
 
-impl<M: PartialEq + Default> Default for WebTermProps<M> {
+impl<A: 'static + Default> Default for WebTermProps<A> {
     fn default() -> Self {
-        Self {
-            inner: M::default(),
-            palette: Palette::default(),
-        }
+        Self::new_with_factory(A::default)
     }
 }
 
 /// Launches the rendering process using the given app state.
-pub fn run_tui<A: TerminalApp>(app: A) {
+pub fn run_tui<A: TerminalApp + Clone>(app: A) {
     yew::Renderer::<WebTerminal<A>>::with_props(WebTermProps::new(app)).render();
 }
 
+/// Launches the rendering process using the app that is returned by the given function. Unlike
+/// [`run_tui`], this does not require the app to be `Clone`.
+pub fn run_tui_with<A, F>(factory: F)
+where
+    A: TerminalApp,
+    F: 'static + Fn() -> A,
+{
+    yew::Renderer::<WebTerminal<A>>::with_props(WebTermProps::new_with_factory(factory)).render();
+}
+
+impl<A> AppFactory<A> {
+    /// Creates a new instance of the app.
+    fn create(&self) -> A {
+        (self.0)()
+    }
+}
+
+/// The app (and its factory) is ignored. A parent that builds its props in its `view` creates a
+/// new factory on every render, which would otherwise re-render the terminal each time.
+impl<A> PartialEq for WebTermProps<A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.palette == other.palette
+            && self.adaptive == other.adaptive
+            && self.style_nonce == other.style_nonce
+            && self.style_mode == other.style_mode
+            && self.custom_glyphs == other.custom_glyphs
+            && self.config == other.config
+            && self.zoom_keys == other.zoom_keys
+            && self.text_input == other.text_input
+            && self.selection == other.selection
+            && self.mouse_capture == other.mouse_capture
+            && self.viewport == other.viewport
+            && self.scrollback == other.scrollback
+    }
+}
+
+impl<A: TerminalApp> WebTerminal<A> {
+    /// The factor by which the font scale changes for each zoom step.
+    const ZOOM_STEP: f64 = 1.1;
+
+    /// Starts, stops, or restarts the ticker to match the app's tick rate.
+    fn sync_ticker(&mut self, ctx: &Context<Self>) {
+        let rate = self.app.tick_rate();
+        if self.ticker.as_ref().map(Ticker::rate) != rate {
+            self.ticker = rate
+                .map(|rate| Ticker::start(rate, ctx.link().callback(|()| WebTermMessage::Tick)));
+        }
+    }
+
+    /// Passes the new size of the grid to the app.
+    fn notify_resize(&mut self) {
+        let area = viewport_area(self.term.get_mut());
+        self.app.resize(area);
+    }
+}
+
+/// Returns the area that the app renders into. This is the whole grid unless the viewport is
+/// inline, in which case it is only the inline rows.
+pub(crate) fn viewport_area(term: &mut Terminal<YewBackend>) -> Rect {
+    // The viewport is only updated once the terminal notices the grid's new size
+    let _ = term.autoresize();
+    term.get_frame().size()
+}
+
 impl<A: TerminalApp> Component for WebTerminal<A> {
     type Message = WebTermMessage<A::Message>;
     type Properties = WebTermProps<A>;
 
     fn create(ctx: &Context<Self>) -> Self {
-        let mut app = ctx.props().inner.clone();
+        let mut app = ctx.props().inner.create();
         app.setup(ctx);
-        let palette = ctx.props().palette;
-        let term = RefCell::new(Terminal::new(YewBackend::new_with_palette(palette)).unwrap());
         /* ---------- Window callback setup --------- */
         let window = web_sys::window().unwrap();
 
+        // If the app follows the OS's color scheme, pick the palette for the current preference,
+        // tell the app about it, and bind a function to the query's "on-change" event
+        let mut palette = ctx.props().palette.clone();
+        let scheme_query = ctx.props().adaptive.as_ref().and_then(|adaptive| {
+            let query = get_color_scheme_query()?;
+            let scheme = if query.matches() {
+                ColorScheme::Dark
+            } else {
+                ColorScheme::Light
+            };
+            palette = adaptive.for_scheme(scheme).clone();
+            app.color_scheme_changed(scheme);
+            query.set_onchange(Some(&process_color_scheme_event(ctx)));
+            Some(query)
+        });
+        let config = ctx.props().config.clone();
+        let mut backend = match ctx.props().style_nonce.as_deref() {
+            Some(nonce) => YewBackend::new_with_style_nonce(config, nonce),
+            None => YewBackend::new_with_config(config),
+        };
+        backend.update_palette(palette);
+        backend.set_style_mode(ctx.props().style_mode);
+        backend.set_custom_glyphs(ctx.props().custom_glyphs);
+        let viewport = ctx.props().viewport;
+        backend.set_viewport(viewport);
+        backend.set_scrollback(ctx.props().scrollback);
+        let viewport = match viewport {
+            // The backend is already sized to a fixed viewport
+            ViewportMode::Fullscreen | ViewportMode::Fixed { .. } => Viewport::Fullscreen,
+            ViewportMode::Inline(height) => Viewport::Inline(height),
+        };
+        let term =
+            RefCell::new(Terminal::with_options(backend, TerminalOptions { viewport }).unwrap());
+
         // Bind a function to the "on-resize" window event
         window.set_onresize(Some(&process_resize_event(ctx)));
 
-        // Bind a function to the "on-wheel" window event
-        window.set_onwheel(Some(&process_wheel_event(ctx)));
+        // Browser zoom changes the device pixel ratio, which changes the size of the grid
+        let ratio_query = watch_pixel_ratio(ctx.link().callback(|()| WebTermMessage::Resized));
+
+        // Bind a function to the "keydown" window event for the zoom keybindings
+        let zoom_keys = ctx.props().zoom_keys.then(|| {
+            let func = process_zoom_key_event(ctx);
+            let _ = window.add_event_listener_with_callback("keydown", &func);
+            func
+        });
 
-        // Bind a function to the "touch-start" window event
-        let acc = Rc::new(RefCell::new(TouchScroll::new()));
-        window.set_ontouchstart(Some(&process_touch_init_event(acc.clone())));
+        let text_input = ctx.props().text_input.then(NodeRef::default);
 
-        // Bind a function to the "touch-move" window event
-        window.set_ontouchmove(Some(&process_touch_move_event(ctx, acc)));
+        // Bind a function to the "paste" window event
+        let paste = {
+            let func = process_paste_event(ctx);
+            window
+                .add_event_listener_with_callback("paste", &func)
+                .ok()
+                .map(|()| func)
+        };
+
+        // Bind a function to the "copy" window event to copy the selected cells
+        let selected = Rc::default();
+        let copy = ctx.props().selection.then(|| {
+            let func = process_copy_event(Rc::clone(&selected));
+            let _ = window.add_event_listener_with_callback("copy", &func);
+            func
+        });
+
+        // Bind functions to the "focus" and "blur" window events
+        let focus = {
+            let gained = process_focus_event(ctx, TermEvent::FocusGained);
+            let lost = process_focus_event(ctx, TermEvent::FocusLost);
+            let _ = window.add_event_listener_with_callback("focus", &gained);
+            let _ = window.add_event_listener_with_callback("blur", &lost);
+            Some((gained, lost))
+        };
+
+        // Bind a function to the "visibilitychange" document event
+        let visibility = window.document().and_then(|document| {
+            let func = process_visibility_event(ctx);
+            document
+                .add_event_listener_with_callback("visibilitychange", &func)
+                .ok()
+                .map(|()| func)
+        });
+
+        // With scrollback, the page scrolls natively instead
+        if ctx.props().scrollback.is_none() {
+            // Bind a function to the "on-wheel" window event
+            window.set_onwheel(Some(&process_wheel_event(ctx)));
+
+            // Bind a function to the "touch-start" window event
+            let acc = Rc::new(RefCell::new(TouchScroll::new()));
+            window.set_ontouchstart(Some(&process_touch_init_event(acc.clone())));
+
+            // Bind a function to the "touch-move" window event
+            window.set_ontouchmove(Some(&process_touch_move_event(ctx, acc)));
+        }
 
-        Self { app, term }
+        let mut digest = Self {
+            app,
+            term,
+            scheme_query,
+            ratio_query,
+            zoom_keys,
+            paste,
+            copy,
+            focus,
+            visibility,
+            selected,
+            text_input,
+            ticker: None,
+            tasks: TaskSet::default(),
+            frames: Some(FrameScheduler::new(
+                ctx.link().callback(|()| WebTermMessage::Redraw),
+            )),
+            force_redraw: false,
+            history_pushed: 0,
+        };
+        digest.sync_ticker(ctx);
+        digest
     }
 
     fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
-        let ctx = TermContext {
+        let term_ctx = TermContext {
             ctx,
             term: self.term.get_mut(),
+            tasks: &mut self.tasks,
+            force_redraw: &mut self.force_redraw,
         };
-        match msg {
-            WebTermMessage::Inner(msg) => self.app.update(ctx, msg),
+        let digest = match msg {
+            WebTermMessage::Inner(msg) => self.app.update(term_ctx, msg),
+            WebTermMessage::Batch(msgs) => msgs.into_iter().fold(false, |render, msg| {
+                let term_ctx = TermContext {
+                    ctx,
+                    term: self.term.get_mut(),
+                    tasks: &mut self.tasks,
+                    force_redraw: &mut self.force_redraw,
+                };
+                self.app.update(term_ctx, msg) || render
+            }),
+            // Ticks and redraws already happen on an animation frame, so they render immediately
+            WebTermMessage::Tick => {
+                let render = self.app.on_tick();
+                self.force_redraw |= render;
+                render
+            }
+            WebTermMessage::Redraw => {
+                self.force_redraw = true;
+                true
+            }
             WebTermMessage::Scrolled(dir) => self.app.scroll(dir),
+            WebTermMessage::Event(event) => self.app.event(event),
+            WebTermMessage::Mouse(kind, x, y, modifiers) => {
+                match self.term.get_mut().backend().cell_at(x, y) {
+                    Some((column, row)) => self.app.event(TermEvent::Mouse(input::MouseEvent {
+                        kind,
+                        column,
+                        row,
+                        modifiers,
+                    })),
+                    None => false,
+                }
+            }
+            WebTermMessage::VisibilityChanged(visibility) => {
+                self.app.visibility_changed(visibility)
+            }
+            WebTermMessage::Select(motion) => {
+                let backend = self.term.get_mut().backend_mut();
+                let old = backend.selection().copied();
+                let new = match motion {
+                    SelectionMotion::Start(x, y, mode) => {
+                        backend.cell_at(x, y).map(|pos| Selection::new(mode, pos))
+                    }
+                    SelectionMotion::Extend(x, y) => old.and_then(|mut selection| {
+                        selection.head = backend.cell_at(x, y)?;
+                        Some(selection)
+                    }),
+                    SelectionMotion::Clear => None,
+                };
+                backend.set_selection(new);
+                old.filter(|s| !s.is_empty()) != new.filter(|s| !s.is_empty())
+            }
             WebTermMessage::Resized => {
-                self.term.get_mut().backend_mut().resize_buffer();
+                if self.term.get_mut().backend_mut().resize_buffer() {
+                    self.notify_resize();
+                }
+                true
+            }
+            WebTermMessage::Zoom(motion) => {
+                let backend = self.term.get_mut().backend_mut();
+                let scale = match motion {
+                    ZoomMotion::In => backend.font_scale() * Self::ZOOM_STEP,
+                    ZoomMotion::Out => backend.font_scale() / Self::ZOOM_STEP,
+                    ZoomMotion::Reset => 1.0,
+                    ZoomMotion::To(scale) => scale,
+                };
+                if backend.set_font_scale(scale) {
+                    self.notify_resize();
+                }
                 true
             }
+            WebTermMessage::ColorSchemeChanged(scheme) => {
+                if let Some(adaptive) = ctx.props().adaptive.as_ref() {
+                    let palette = adaptive.for_scheme(scheme).clone();
+                    self.term.get_mut().backend_mut().update_palette(palette);
+                }
+                self.app.color_scheme_changed(scheme);
+                true
+            }
+        };
+        self.sync_ticker(ctx);
+        if std::mem::take(&mut self.force_redraw) {
+            if let Some(frames) = self.frames.as_ref() {
+                frames.cancel();
+            }
+            return true;
+        }
+        match self.frames.as_ref() {
+            Some(frames) if digest => {
+                frames.schedule();
+                false
+            }
+            _ => digest,
+        }
+    }
+
+    fn destroy(&mut self, _ctx: &Context<Self>) {
+        self.tasks.abort_all();
+        self.ticker = None;
+        self.frames = None;
+        if let Some(query) = self.scheme_query.take() {
+            query.set_onchange(None);
+        }
+        if let Some(query) = self.ratio_query.borrow_mut().take() {
+            query.set_onchange(None);
+        }
+        if let Some(window) = web_sys::window() {
+            if let Some(func) = self.zoom_keys.take() {
+                let _ = window.remove_event_listener_with_callback("keydown", &func);
+            }
+            if let Some(func) = self.paste.take() {
+                let _ = window.remove_event_listener_with_callback("paste", &func);
+            }
+            if let Some(func) = self.copy.take() {
+                let _ = window.remove_event_listener_with_callback("copy", &func);
+            }
+            if let Some((gained, lost)) = self.focus.take() {
+                let _ = window.remove_event_listener_with_callback("focus", &gained);
+                let _ = window.remove_event_listener_with_callback("blur", &lost);
+            }
+            if let Some((document, func)) = window.document().zip(self.visibility.take()) {
+                let _ = document.remove_event_listener_with_callback("visibilitychange", &func);
+            }
         }
     }
 
     fn view(&self, ctx: &Context<Self>) -> yew::Html {
         let mut term = self.term.borrow_mut();
-        let area = term.size().unwrap();
-        term.draw(|frame| self.app.render(area, frame)).unwrap();
-        term.backend_mut()
-            .hydrate(|span| self.app.hydrate(ctx, span))
+        let mut area = Rect::default();
+        term.draw(|frame| {
+            area = frame.size();
+            self.app.render(area, frame)
+        })
+        .unwrap();
+        let selection = ctx.props().selection;
+        if selection {
+            let backend = term.backend_mut();
+            backend.set_unselectable(self.app.unselectable(area));
+            *self.selected.borrow_mut() = backend.selected_text();
+        }
+        let input = self.text_input.as_ref().map(|node| {
+            let zoom_keys = ctx.props().zoom_keys;
+            text_input(ctx, node.clone(), Rc::clone(&self.selected), zoom_keys)
+        });
+        let rendered = term.backend_mut().hydrate_with_input(
+            |span| self.app.hydrate(ctx, span),
+            |region| self.app.embed(ctx, region),
+            input,
+        );
+        let mouse = ctx.props().mouse_capture;
+        if self.text_input.is_none() && !selection && !mouse {
+            return rendered;
+        }
+        // Clicking anywhere on the terminal gives the focus to the text input
+        let onclick = self.text_input.clone().map(|node| {
+            Callback::from(move |_: MouseEvent| {
+                if let Some(area) = node.cast::<HtmlTextAreaElement>() {
+                    let _ = area.focus();
+                }
+            })
+        });
+        let to_mouse = |kind, event: &MouseEvent| {
+            let modifiers = KeyModifiers::from_mouse(event);
+            WebTermMessage::Mouse(kind, event.client_x(), event.client_y(), modifiers)
+        };
+        let onmousedown = (selection || mouse).then(|| {
+            ctx.link().batch_callback(move |event: MouseEvent| {
+                let mut digest = Vec::with_capacity(2);
+                if selection && event.button() == 0 {
+                    let mode = if event.alt_key() {
+                        SelectionMode::Block
+                    } else {
+                        SelectionMode::Line
+                    };
+                    digest.push(WebTermMessage::Select(SelectionMotion::Start(
+                        event.client_x(),
+                        event.client_y(),
+                        mode,
+                    )));
+                }
+                if let Some(button) = MouseButton::from_button(event.button()).filter(|_| mouse) {
+                    digest.push(to_mouse(MouseEventKind::Down(button), &event));
+                }
+                digest
+            })
+        });
+        let onmousemove = (selection || mouse).then(|| {
+            ctx.link().batch_callback(move |event: MouseEvent| {
+                let mut digest = Vec::with_capacity(2);
+                // Only drags (with the primary button held) extend the selection
+                if selection && event.buttons() & 1 == 1 {
+                    digest.push(WebTermMessage::Select(SelectionMotion::Extend(
+                        event.client_x(),
+                        event.client_y(),
+                    )));
+                }
+                if mouse {
+                    let kind = match MouseButton::from_buttons(event.buttons()) {
+                        Some(button) => MouseEventKind::Drag(button),
+                        None => MouseEventKind::Moved,
+                    };
+                    digest.push(to_mouse(kind, &event));
+                }
+                digest
+            })
+        });
+        let onmouseup = mouse.then(|| {
+            ctx.link().batch_callback(move |event: MouseEvent| {
+                MouseButton::from_button(event.button())
+                    .map(|button| to_mouse(MouseEventKind::Up(button), &event))
+            })
+        });
+        // The browser's own selection is replaced by the terminal's
+        let class = selection.then_some(NO_SELECT_CLASS);
+        html! {
+            <div { onclick } { onmousedown } { onmousemove } { onmouseup } { class }>
+                { rendered }
+            </div>
+        }
+    }
+
+    fn rendered(&mut self, ctx: &Context<Self>, first_render: bool) {
+        // Keep the terminal in view as its history grows
+        let history_pushed = self.term.get_mut().backend().history_pushed();
+        if std::mem::replace(&mut self.history_pushed, history_pushed) < history_pushed {
+            if let Some(window) = web_sys::window() {
+                let height = window
+                    .document()
+                    .and_then(|d| d.document_element())
+                    .map(|e| e.scroll_height())
+                    .unwrap_or_default();
+                window.scroll_to_with_x_and_y(0.0, height as f64);
+            }
+        }
+        if !first_render {
+            return;
+        }
+        if let Some(area) = self
+            .text_input
+            .as_ref()
+            .and_then(|node| node.cast::<HtmlTextAreaElement>())
+        {
+            let cb = ctx.link().callback(WebTermMessage::Event);
+            let func = process_composition_end_event(cb);
+            let _ = area.add_event_listener_with_callback("compositionend", &func);
+            reset_input(&area);
+            let _ = area.focus();
+        }
     }
 }
@@ -0,0 +1,276 @@
+use std::{borrow::Cow, fmt::Display};
+
+use base16_palettes::{Base16Color, Base16Palette, Palette};
+
+use crate::ColorScheme;
+
+/// The palette used by the backend to render indexed colors. This is either one of the presets
+/// provided by [`base16_palettes`] or a user-defined [`CustomPalette`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TermPalette {
+    /// One of the palettes defined by the `base16_palettes` crate.
+    Preset(Palette),
+    /// A palette that was defined at runtime, e.g. loaded from a base16 scheme file.
+    Custom(CustomPalette),
+}
+
+/// A pair of palettes that the terminal switches between as the OS's light/dark preference (i.e.
+/// the `prefers-color-scheme` media query) changes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdaptivePalette {
+    /// The palette used when the OS prefers a light color scheme.
+    pub light: TermPalette,
+    /// The palette used when the OS prefers a dark color scheme.
+    pub dark: TermPalette,
+}
+
+/// A user-defined base16 palette. Each of the 16 base16 colors is mapped to an RGB value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CustomPalette {
+    colors: [(u8, u8, u8); 16],
+}
+
+/// The errors that can occur while constructing a [`CustomPalette`] from text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PaletteError {
+    /// The given string could not be parsed as a 6-digit hex color.
+    InvalidHex(String),
+    /// The scheme did not define the color with the given index (i.e. `base0X`).
+    MissingColor(u8),
+}
+
+impl TermPalette {
+    /// Returns the hex string (including the leading `#`) for the given color.
+    pub fn to_hex_str(&self, color: Base16Color) -> Cow<'static, str> {
+        match self {
+            TermPalette::Preset(p) => p.to_hex_str(color).into(),
+            TermPalette::Custom(p) => p.to_hex_str(color).into(),
+        }
+    }
+
+    /// Returns the RGB values for the given color.
+    pub fn to_rgb(&self, color: Base16Color) -> (u8, u8, u8) {
+        match self {
+            TermPalette::Preset(p) => p.to_rgb(color),
+            TermPalette::Custom(p) => p.to_rgb(color),
+        }
+    }
+}
+
+impl Default for TermPalette {
+    fn default() -> Self {
+        Self::Preset(Palette::default())
+    }
+}
+
+impl From<Palette> for TermPalette {
+    fn from(value: Palette) -> Self {
+        Self::Preset(value)
+    }
+}
+
+impl From<CustomPalette> for TermPalette {
+    fn from(value: CustomPalette) -> Self {
+        Self::Custom(value)
+    }
+}
+
+impl AdaptivePalette {
+    /// Creates a pair of palettes from the given light and dark palettes.
+    pub fn new<L: Into<TermPalette>, D: Into<TermPalette>>(light: L, dark: D) -> Self {
+        Self {
+            light: light.into(),
+            dark: dark.into(),
+        }
+    }
+
+    /// Returns the palette that should be used for the given color scheme.
+    pub fn for_scheme(&self, scheme: ColorScheme) -> &TermPalette {
+        match scheme {
+            ColorScheme::Light => &self.light,
+            ColorScheme::Dark => &self.dark,
+        }
+    }
+}
+
+impl CustomPalette {
+    /// Creates a palette from 16 RGB values. The colors are ordered by their base16 index, i.e.
+    /// `base00` first and `base0F` last.
+    pub fn new(colors: [(u8, u8, u8); 16]) -> Self {
+        Self { colors }
+    }
+
+    /// Creates a palette from 16 hex strings, ordered by their base16 index. A leading `#` is
+    /// optional.
+    pub fn from_hex<S: AsRef<str>>(colors: [S; 16]) -> Result<Self, PaletteError> {
+        let mut digest = [(0, 0, 0); 16];
+        for (slot, color) in digest.iter_mut().zip(colors.iter()) {
+            *slot = parse_hex(color.as_ref())?;
+        }
+        Ok(Self::new(digest))
+    }
+
+    /// Parses a base16 scheme, such as the YAML files found in the base16 scheme repositories or
+    /// their JSON equivalents. Only the `base00` through `base0F` keys are read; all other keys
+    /// (e.g. `scheme` and `author`) are ignored.
+    pub fn from_base16_scheme(scheme: &str) -> Result<Self, PaletteError> {
+        let mut colors: [Option<(u8, u8, u8)>; 16] = [None; 16];
+        for entry in scheme.split(['\n', ',', '{', '}']) {
+            let Some((key, value)) = entry.split_once(':') else {
+                continue;
+            };
+            let key = key.trim().trim_matches(['"', '\'']);
+            let Some(index) = key
+                .strip_prefix("base0")
+                .filter(|i| i.len() == 1)
+                .and_then(|i| u8::from_str_radix(i, 16).ok())
+            else {
+                continue;
+            };
+            let value = value.trim();
+            let value = match value.strip_prefix(['"', '\'']) {
+                Some(quoted) => quoted.split(['"', '\'']).next().unwrap_or_default(),
+                // YAML allows for trailing comments on unquoted values
+                None => value.split_whitespace().next().unwrap_or_default(),
+            };
+            colors[index as usize] = Some(parse_hex(value)?);
+        }
+        let mut digest = [(0, 0, 0); 16];
+        for (i, (slot, color)) in digest.iter_mut().zip(colors).enumerate() {
+            *slot = color.ok_or(PaletteError::MissingColor(i as u8))?;
+        }
+        Ok(Self::new(digest))
+    }
+
+    /// Replaces a single color in the palette. This is useful for tweaking a handful of colors in
+    /// an existing palette.
+    pub fn set_color(&mut self, color: Base16Color, rgb: (u8, u8, u8)) {
+        self.colors[color.index() as usize] = rgb;
+    }
+
+    /// Returns the RGB values for the given color.
+    pub fn to_rgb(&self, color: Base16Color) -> (u8, u8, u8) {
+        self.colors[color.index() as usize]
+    }
+
+    /// Returns the hex string (including the leading `#`) for the given color.
+    pub fn to_hex_str(&self, color: Base16Color) -> String {
+        let (r, g, b) = self.to_rgb(color);
+        format!("#{r:02x}{g:02x}{b:02x}")
+    }
+}
+
+impl From<Palette> for CustomPalette {
+    fn from(value: Palette) -> Self {
+        let mut colors = [(0, 0, 0); 16];
+        for (i, slot) in colors.iter_mut().enumerate() {
+            *slot = value.to_rgb(Base16Color::from_index(i as u8));
+        }
+        Self::new(colors)
+    }
+}
+
+/// Parses a 6-digit hex color with an optional leading `#`.
+fn parse_hex(s: &str) -> Result<(u8, u8, u8), PaletteError> {
+    let hex = s.trim().trim_start_matches('#');
+    let err = || PaletteError::InvalidHex(s.to_owned());
+    // `from_str_radix` accepts a leading sign, so the digits are checked up front
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(err());
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| err());
+    Ok((channel(0)?, channel(2)?, channel(4)?))
+}
+
+impl Display for PaletteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PaletteError::InvalidHex(s) => write!(f, "'{s}' is not a valid hex color"),
+            PaletteError::MissingColor(i) => write!(f, "the scheme does not define base0{i:X}"),
+        }
+    }
+}
+
+impl std::error::Error for PaletteError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a YAML scheme that defines every color as `#XXYYZZ` where each channel is the index.
+    fn yaml_scheme() -> String {
+        let mut scheme = String::from("# A comment\nscheme: \"Test: Scheme\"\nauthor: 'Someone'\n");
+        for i in 0..16 {
+            scheme.push_str(&format!(
+                "base0{i:X}: \"{i:02x}{i:02x}{i:02x}\" # color {i}\n"
+            ));
+        }
+        scheme
+    }
+
+    fn indexed_colors() -> [(u8, u8, u8); 16] {
+        std::array::from_fn(|i| (i as u8, i as u8, i as u8))
+    }
+
+    #[test]
+    fn parses_yaml_with_comments_and_quotes() {
+        let palette = CustomPalette::from_base16_scheme(&yaml_scheme()).unwrap();
+        assert_eq!(palette, CustomPalette::new(indexed_colors()));
+    }
+
+    #[test]
+    fn parses_unquoted_yaml_values() {
+        let scheme = yaml_scheme().replace('"', "");
+        let palette = CustomPalette::from_base16_scheme(&scheme).unwrap();
+        assert_eq!(palette, CustomPalette::new(indexed_colors()));
+    }
+
+    #[test]
+    fn parses_json() {
+        let entries: Vec<_> = (0..16)
+            .map(|i| format!("\"base0{i:X}\": \"#{i:02x}{i:02x}{i:02x}\""))
+            .collect();
+        let scheme = format!("{{\"scheme\": \"Test\", {}}}", entries.join(", "));
+        let palette = CustomPalette::from_base16_scheme(&scheme).unwrap();
+        assert_eq!(palette, CustomPalette::new(indexed_colors()));
+    }
+
+    #[test]
+    fn reports_missing_colors() {
+        let scheme = yaml_scheme().replace("base0A:", "base0Z:");
+        assert_eq!(
+            CustomPalette::from_base16_scheme(&scheme),
+            Err(PaletteError::MissingColor(0xA))
+        );
+    }
+
+    #[test]
+    fn reports_invalid_colors() {
+        let scheme = yaml_scheme().replace("\"030303\"", "\"03030\"");
+        assert_eq!(
+            CustomPalette::from_base16_scheme(&scheme),
+            Err(PaletteError::InvalidHex("03030".into()))
+        );
+    }
+
+    #[test]
+    fn parses_hex() {
+        assert_eq!(parse_hex("#1a2B3c"), Ok((0x1A, 0x2B, 0x3C)));
+        assert_eq!(parse_hex("FFFFFF"), Ok((0xFF, 0xFF, 0xFF)));
+        assert_eq!(parse_hex(" 000000 "), Ok((0, 0, 0)));
+    }
+
+    #[test]
+    fn rejects_bad_hex() {
+        for bad in ["", "#fff", "1234567", "12345g", "+1+2+3", "#ééé"] {
+            assert_eq!(parse_hex(bad), Err(PaletteError::InvalidHex(bad.into())));
+        }
+    }
+
+    #[test]
+    fn formats_hex_with_padding() {
+        let mut palette = CustomPalette::new(indexed_colors());
+        palette.set_color(Base16Color::from_index(0), (1, 2, 3));
+        assert_eq!(palette.to_hex_str(Base16Color::from_index(0)), "#010203");
+    }
+}
@@ -1,2 +1,7 @@
 pub use crate::*;
 pub use backend::*;
+pub use config::*;
+pub use embed::*;
+pub use input::*;
+pub use palette::*;
+pub use selection::*;
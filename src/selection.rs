@@ -0,0 +1,242 @@
+use std::ops::Range;
+
+use ratatui::{buffer::Cell, prelude::Rect};
+use unicode_width::UnicodeWidthStr;
+use yew::{html, Html};
+
+/// Determines which cells lie between the two ends of a selection.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SelectionMode {
+    /// Selects text like a text editor. The selection runs from the first end to the end of its
+    /// line, covers every line in between, and stops at the second end.
+    #[default]
+    Line,
+    /// Selects the rectangle of cells that has the two ends as its corners. This is useful for
+    /// copying a single column of a table or the contents of a bordered widget.
+    Block,
+}
+
+/// A selection of cells in the terminal. Selections are made against the terminal's cells rather
+/// than the rendered HTML, so copying one yields exactly the text that is displayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Selection {
+    /// How the cells between the ends are selected.
+    pub mode: SelectionMode,
+    /// The cell where the selection was started.
+    pub anchor: (u16, u16),
+    /// The cell where the selection currently ends.
+    pub head: (u16, u16),
+}
+
+/// The ways that the user can change the selection. Positions are the client coordinates (in
+/// pixels) of the mouse, which the backend converts into cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectionMotion {
+    /// Starts a new selection at the given position, replacing the old selection.
+    Start(i32, i32, SelectionMode),
+    /// Moves the head of the selection to the given position.
+    Extend(i32, i32),
+    /// Removes the selection.
+    Clear,
+}
+
+impl Selection {
+    /// Creates an empty selection that starts (and ends) at the given cell.
+    pub fn new(mode: SelectionMode, anchor: (u16, u16)) -> Self {
+        Self {
+            mode,
+            anchor,
+            head: anchor,
+        }
+    }
+
+    /// Returns if the selection does not cover any cells, i.e. the user has clicked without
+    /// dragging.
+    pub fn is_empty(&self) -> bool {
+        self.anchor == self.head
+    }
+
+    /// Returns the columns of the given row that are covered by the selection, ignoring any
+    /// unselectable regions.
+    fn row_range(&self, y: u16, width: u16) -> Option<Range<u16>> {
+        let ((ax, ay), (hx, hy)) = (self.anchor, self.head);
+        if y < ay.min(hy) || y > ay.max(hy) {
+            return None;
+        }
+        let range = match self.mode {
+            SelectionMode::Line => {
+                // Order the ends by their position in the text
+                let ((sx, sy), (ex, ey)) = if (ay, ax) <= (hy, hx) {
+                    (self.anchor, self.head)
+                } else {
+                    (self.head, self.anchor)
+                };
+                let start = if y == sy { sx } else { 0 };
+                let end = if y == ey { ex + 1 } else { width };
+                start..end
+            }
+            SelectionMode::Block => ax.min(hx)..ax.max(hx) + 1,
+        };
+        Some(range.start.min(width)..range.end.min(width))
+    }
+
+    /// Returns the runs of selected cells in the given row, with the unselectable regions cut
+    /// out.
+    fn row_runs(&self, y: u16, width: u16, unselectable: &[Rect]) -> Vec<Range<u16>> {
+        let Some(range) = self.row_range(y, width) else {
+            return Vec::new();
+        };
+        let mut digest = vec![range];
+        for rect in unselectable.iter().filter(|r| r.y <= y && y < r.bottom()) {
+            let (cut_start, cut_end) = (rect.x, rect.right());
+            digest = digest
+                .into_iter()
+                .flat_map(|run| {
+                    [
+                        run.start..run.end.min(cut_start),
+                        run.start.max(cut_end)..run.end,
+                    ]
+                })
+                .filter(|run| !run.is_empty())
+                .collect();
+        }
+        digest
+    }
+
+    /// Returns the text in the selected cells. Trailing whitespace is removed from each line, and
+    /// the placeholder cells that ratatui leaves after wide characters are skipped.
+    pub(crate) fn text(&self, buffer: &[Vec<Cell>], unselectable: &[Rect]) -> String {
+        let mut lines = Vec::new();
+        for (y, line) in buffer.iter().enumerate() {
+            let runs = self.row_runs(y as u16, line.len() as u16, unselectable);
+            if runs.is_empty() {
+                continue;
+            }
+            let mut text = String::new();
+            let mut covered = 0;
+            for (x, cell) in line.iter().enumerate() {
+                if covered > 0 {
+                    covered -= 1;
+                    continue;
+                }
+                covered = cell.symbol().width().saturating_sub(1);
+                if runs.iter().any(|run| run.contains(&(x as u16))) {
+                    match cell.symbol() {
+                        "" => text.push(' '),
+                        symbol => text.push_str(symbol),
+                    }
+                }
+            }
+            lines.push(text.trim_end().to_owned());
+        }
+        lines.join("\n")
+    }
+
+    /// Renders the highlight over the selected cells.
+    pub(crate) fn render(
+        &self,
+        (width, height): (u16, u16),
+        unselectable: &[Rect],
+        (cell_w, cell_h): (f64, f64),
+    ) -> Vec<Html> {
+        (0..height)
+            .flat_map(|y| {
+                self.row_runs(y, width, unselectable)
+                    .into_iter()
+                    .map(move |run| (y, run))
+            })
+            .map(|(y, run)| {
+                let style = format!(
+                    "position: absolute; left: {}px; top: {}px; width: {}px; height: {cell_h}px; background: currentColor; opacity: 0.35; pointer-events: none;",
+                    run.start as f64 * cell_w,
+                    y as f64 * cell_h,
+                    run.len() as f64 * cell_w,
+                );
+                html! { <div { style } /> }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a buffer from the given rows. Wide characters are followed by a placeholder cell, as
+    /// ratatui does.
+    fn buffer(rows: &[&str]) -> Vec<Vec<Cell>> {
+        rows.iter()
+            .map(|row| {
+                let mut line = Vec::new();
+                for c in row.chars() {
+                    let mut cell = Cell::default();
+                    cell.set_char(c);
+                    let width = c.to_string().width();
+                    line.push(cell);
+                    line.extend((1..width).map(|_| Cell::default()));
+                }
+                line
+            })
+            .collect()
+    }
+
+    fn selection(mode: SelectionMode, anchor: (u16, u16), head: (u16, u16)) -> Selection {
+        Selection { mode, anchor, head }
+    }
+
+    const ROWS: [&str; 3] = ["hello world", "second line", "third"];
+
+    #[test]
+    fn selects_lines() {
+        let buffer = buffer(&ROWS);
+        let forward = selection(SelectionMode::Line, (6, 0), (5, 1));
+        assert_eq!(forward.text(&buffer, &[]), "world\nsecond");
+        let backward = selection(SelectionMode::Line, (5, 1), (6, 0));
+        assert_eq!(backward.text(&buffer, &[]), "world\nsecond");
+    }
+
+    #[test]
+    fn selects_blocks() {
+        let buffer = buffer(&ROWS);
+        let forward = selection(SelectionMode::Block, (2, 0), (4, 2));
+        assert_eq!(forward.text(&buffer, &[]), "llo\ncon\nird");
+        let reversed = selection(SelectionMode::Block, (4, 0), (2, 2));
+        assert_eq!(reversed.text(&buffer, &[]), "llo\ncon\nird");
+    }
+
+    #[test]
+    fn trims_trailing_whitespace() {
+        let buffer = buffer(&["ab   ", "cd   "]);
+        let block = selection(SelectionMode::Block, (0, 0), (4, 1));
+        assert_eq!(block.text(&buffer, &[]), "ab\ncd");
+    }
+
+    #[test]
+    fn cuts_out_unselectable_regions() {
+        let buffer = buffer(&ROWS);
+        let unselectable = [Rect::new(5, 0, 1, 2), Rect::new(0, 1, 2, 1)];
+        let line = selection(SelectionMode::Line, (0, 0), (10, 1));
+        assert_eq!(line.row_runs(0, 11, &unselectable), [0..5, 6..11]);
+        assert_eq!(line.row_runs(1, 11, &unselectable), [2..5, 6..11]);
+        assert_eq!(line.row_runs(2, 11, &unselectable), []);
+        assert_eq!(line.text(&buffer, &unselectable), "helloworld\ncon line");
+    }
+
+    #[test]
+    fn skips_wide_character_placeholders() {
+        let buffer = buffer(&["a中文b"]);
+        assert_eq!(buffer[0].len(), 6);
+        let line = selection(SelectionMode::Line, (0, 0), (5, 0));
+        assert_eq!(line.text(&buffer, &[]), "a中文b");
+        // Selecting only the placeholder of a wide character does not copy it
+        let placeholder = selection(SelectionMode::Line, (2, 0), (2, 0));
+        assert_eq!(placeholder.text(&buffer, &[]), "");
+    }
+
+    #[test]
+    fn clamps_to_the_width() {
+        let line = selection(SelectionMode::Block, (8, 0), (20, 0));
+        assert_eq!(line.row_runs(0, 10, &[]), vec![8..10]);
+        assert_eq!(line.row_runs(1, 10, &[]), []);
+    }
+}
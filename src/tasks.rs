@@ -0,0 +1,38 @@
+use std::{cell::RefCell, collections::HashMap, future::Future, rc::Rc};
+
+use futures::future::{AbortHandle, Abortable};
+
+/// The futures that have been spawned on behalf of an app. Each future is abortable, and any that
+/// are still running when the terminal is destroyed are aborted so that they can't send messages
+/// to a component that no longer exists.
+#[derive(Debug, Default)]
+pub(crate) struct TaskSet {
+    next_id: u64,
+    /// The handles of the tasks that are still running. Tasks remove themselves once they finish.
+    handles: Rc<RefCell<HashMap<u64, AbortHandle>>>,
+}
+
+impl TaskSet {
+    /// Spawns the future onto the browser's event loop.
+    pub(crate) fn spawn<F>(&mut self, future: F)
+    where
+        F: 'static + Future<Output = ()>,
+    {
+        let (handle, registration) = AbortHandle::new_pair();
+        let id = self.next_id;
+        self.next_id += 1;
+        self.handles.borrow_mut().insert(id, handle);
+        let handles = Rc::clone(&self.handles);
+        wasm_bindgen_futures::spawn_local(async move {
+            let _ = Abortable::new(future, registration).await;
+            handles.borrow_mut().remove(&id);
+        });
+    }
+
+    /// Aborts all of the running tasks.
+    pub(crate) fn abort_all(&mut self) {
+        // The handles are taken first since aborted tasks remove themselves from the map
+        let handles = std::mem::take(&mut *self.handles.borrow_mut());
+        handles.into_values().for_each(|handle| handle.abort());
+    }
+}
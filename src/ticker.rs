@@ -0,0 +1,132 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+    time::Duration,
+};
+
+use web_sys::{
+    js_sys::Function,
+    wasm_bindgen::{prelude::Closure, JsCast},
+};
+use yew::Callback;
+
+/// The type of the closure that is given to `requestAnimationFrame`.
+type FrameClosure = Closure<dyn FnMut(f64)>;
+
+/// Drives an app's ticks. Ticks are checked for on every animation frame, so at most one tick (and
+/// therefore one redraw) happens per frame no matter how small the tick rate is. Animation frames
+/// are paused while the page is hidden, and any ticks that were missed are coalesced into one.
+pub(crate) struct Ticker {
+    rate: Duration,
+    /// The ID of the most recently requested animation frame.
+    frame: Rc<Cell<i32>>,
+    /// The closure that is run on every animation frame. It re-requests itself, so it holds onto
+    /// this slot as well. The slot is emptied when the ticker is dropped, breaking that cycle.
+    closure: Rc<RefCell<Option<FrameClosure>>>,
+}
+
+impl Ticker {
+    /// Starts ticking at the given rate. The callback is called for each tick.
+    pub(crate) fn start(rate: Duration, cb: Callback<()>) -> Self {
+        let frame = Rc::new(Cell::new(0));
+        let closure: Rc<RefCell<Option<FrameClosure>>> = Rc::default();
+        let rate_ms = rate.as_secs_f64() * 1000.0;
+        let mut last: Option<f64> = None;
+        let slot = Rc::clone(&closure);
+        let id = Rc::clone(&frame);
+        let func = move |now: f64| {
+            let elapsed = now - *last.get_or_insert(now);
+            if elapsed >= rate_ms {
+                // Stay on schedule unless ticks were missed, in which case the schedule restarts
+                let behind = elapsed >= 2.0 * rate_ms;
+                last = Some(if behind {
+                    now
+                } else {
+                    now - (elapsed - rate_ms)
+                });
+                cb.emit(());
+            }
+            if let Some(closure) = slot.borrow().as_ref() {
+                id.set(request_frame(closure));
+            }
+        };
+        let func: FrameClosure = Closure::new(func);
+        frame.set(request_frame(&func));
+        *closure.borrow_mut() = Some(func);
+        Self {
+            rate,
+            frame,
+            closure,
+        }
+    }
+
+    /// Returns the rate at which the ticker ticks.
+    pub(crate) fn rate(&self) -> Duration {
+        self.rate
+    }
+}
+
+impl Drop for Ticker {
+    fn drop(&mut self) {
+        if let Some(window) = web_sys::window() {
+            let _ = window.cancel_animation_frame(self.frame.get());
+        }
+        self.closure.borrow_mut().take();
+    }
+}
+
+/// Schedules redraws so that the terminal renders at most once per animation frame, no matter how
+/// many updates ask for a render in between.
+pub(crate) struct FrameScheduler {
+    /// The ID of the requested animation frame, if one is pending.
+    frame: Rc<Cell<Option<i32>>>,
+    /// The function that is run on the requested frame.
+    func: Function,
+}
+
+impl FrameScheduler {
+    /// Creates a scheduler that calls the callback on each frame that a redraw was requested for.
+    pub(crate) fn new(cb: Callback<()>) -> Self {
+        let frame = Rc::new(Cell::new(None));
+        let pending = Rc::clone(&frame);
+        let func = move || {
+            pending.set(None);
+            cb.emit(());
+        };
+        let func = Closure::<dyn 'static + Fn()>::new(func)
+            .into_js_value()
+            .into();
+        Self { frame, func }
+    }
+
+    /// Requests a redraw on the next animation frame, unless one is already pending.
+    pub(crate) fn schedule(&self) {
+        if self.frame.get().is_none() {
+            let id = web_sys::window().and_then(|w| w.request_animation_frame(&self.func).ok());
+            self.frame.set(id);
+        }
+    }
+
+    /// Cancels the pending redraw, if there is one.
+    pub(crate) fn cancel(&self) {
+        if let Some((window, id)) = web_sys::window().zip(self.frame.take()) {
+            let _ = window.cancel_animation_frame(id);
+        }
+    }
+}
+
+impl Drop for FrameScheduler {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+/// Requests that the closure be run on the next animation frame and returns the frame's ID.
+fn request_frame(closure: &FrameClosure) -> i32 {
+    web_sys::window()
+        .and_then(|w| {
+            w.request_animation_frame(closure.as_ref().unchecked_ref())
+                .ok()
+        })
+        .unwrap_or_default()
+}
@@ -1,13 +1,17 @@
 use std::{cell::RefCell, cmp::Ordering, rc::Rc};
 
 use web_sys::{
-    js_sys::Function,
-    wasm_bindgen::{prelude::Closure, JsValue},
-    TouchEvent, WheelEvent,
+    js_sys::{Function, Reflect},
+    wasm_bindgen::{prelude::Closure, JsCast, JsValue},
+    ClipboardEvent, HtmlElement, HtmlInputElement, HtmlTextAreaElement, KeyboardEvent,
+    MediaQueryList, MediaQueryListEvent, TouchEvent, WheelEvent,
 };
-use yew::Context;
+use yew::{Callback, Context};
 
-use crate::{ScrollMotion, TerminalApp, WebTermMessage, WebTerminal};
+use crate::{
+    input::TermEvent, ColorScheme, ScrollMotion, TerminalApp, Visibility, WebTermMessage,
+    WebTerminal, ZoomMotion,
+};
 
 pub(crate) fn process_resize_event<A: TerminalApp>(ctx: &Context<WebTerminal<A>>) -> Function {
     let cb = ctx.link().callback(|()| WebTermMessage::Resized);
@@ -32,6 +36,182 @@ pub(crate) fn process_wheel_event<A: TerminalApp>(ctx: &Context<WebTerminal<A>>)
         .into()
 }
 
+pub(crate) fn process_focus_event<A: TerminalApp>(
+    ctx: &Context<WebTerminal<A>>,
+    event: TermEvent,
+) -> Function {
+    let cb = ctx.link().callback(WebTermMessage::Event);
+    let func = move || cb.emit(event.clone());
+    Closure::<dyn 'static + Fn()>::new(func)
+        .into_js_value()
+        .into()
+}
+
+/// Returns whether the page is currently visible.
+pub(crate) fn get_visibility() -> Visibility {
+    let hidden = web_sys::window()
+        .and_then(|w| w.document())
+        .is_some_and(|d| d.hidden());
+    if hidden {
+        Visibility::Hidden
+    } else {
+        Visibility::Visible
+    }
+}
+
+pub(crate) fn process_visibility_event<A: TerminalApp>(ctx: &Context<WebTerminal<A>>) -> Function {
+    let cb = ctx.link().callback(WebTermMessage::VisibilityChanged);
+    let func = move || cb.emit(get_visibility());
+    Closure::<dyn 'static + Fn()>::new(func)
+        .into_js_value()
+        .into()
+}
+
+/// Returns the media query list that tracks if the OS prefers a dark color scheme.
+pub(crate) fn get_color_scheme_query() -> Option<MediaQueryList> {
+    web_sys::window()?
+        .match_media("(prefers-color-scheme: dark)")
+        .ok()
+        .flatten()
+}
+
+pub(crate) fn process_color_scheme_event<A: TerminalApp>(
+    ctx: &Context<WebTerminal<A>>,
+) -> Function {
+    let cb = ctx.link().callback(WebTermMessage::ColorSchemeChanged);
+    let func = move |event: JsValue| {
+        let event: MediaQueryListEvent = event.into();
+        let scheme = if event.matches() {
+            ColorScheme::Dark
+        } else {
+            ColorScheme::Light
+        };
+        cb.emit(scheme);
+    };
+    Closure::<dyn 'static + Fn(JsValue)>::new(func)
+        .into_js_value()
+        .into()
+}
+
+/// Watches for changes to the device pixel ratio (e.g. from browser zoom) and emits to the given
+/// callback when it does. Media queries can only match a specific ratio, so a new query is
+/// registered each time the ratio changes. The returned slot always holds the active query.
+pub(crate) fn watch_pixel_ratio(cb: Callback<()>) -> Rc<RefCell<Option<MediaQueryList>>> {
+    fn register(cb: Callback<()>, slot: Rc<RefCell<Option<MediaQueryList>>>) {
+        let Some(window) = web_sys::window() else {
+            return;
+        };
+        let query = format!("(resolution: {}dppx)", window.device_pixel_ratio());
+        let Some(list) = window.match_media(&query).ok().flatten() else {
+            return;
+        };
+        let inner = slot.clone();
+        let func = move || {
+            cb.emit(());
+            register(cb.clone(), inner.clone());
+        };
+        let func: Function = Closure::<dyn 'static + Fn()>::new(func)
+            .into_js_value()
+            .into();
+        list.set_onchange(Some(&func));
+        if let Some(old) = slot.borrow_mut().replace(list) {
+            old.set_onchange(None);
+        }
+    }
+
+    let slot = Rc::default();
+    register(cb, Rc::clone(&slot));
+    slot
+}
+
+/// Writes the given text to the system clipboard, if the browser exposes it.
+pub(crate) fn write_clipboard(text: &str) -> bool {
+    let Some(navigator) = web_sys::window().map(|w| w.navigator()) else {
+        return false;
+    };
+    // `navigator.clipboard` is undefined outside of secure contexts
+    if !Reflect::has(&navigator, &JsValue::from_str("clipboard")).unwrap_or(false) {
+        return false;
+    }
+    let _ = navigator.clipboard().write_text(text);
+    true
+}
+
+/// Copies the selected cells, if there are any. Otherwise, the browser's copy is left alone.
+pub(crate) fn process_copy_event(selected: Rc<RefCell<Option<String>>>) -> Function {
+    let func = move |event: JsValue| {
+        let event: ClipboardEvent = event.into();
+        let selected = selected.borrow();
+        let (Some(text), Some(data)) = (selected.as_deref(), event.clipboard_data()) else {
+            return;
+        };
+        if data.set_data("text/plain", text).is_ok() {
+            event.prevent_default();
+        }
+    };
+    Closure::<dyn 'static + Fn(JsValue)>::new(func)
+        .into_js_value()
+        .into()
+}
+
+/// Forwards pastes to the app. Pastes into other editable elements on the page (e.g. an `<input>`
+/// that the app embedded) are left alone. This includes the terminal's own text input, which
+/// handles its pastes itself.
+pub(crate) fn process_paste_event<A: TerminalApp>(ctx: &Context<WebTerminal<A>>) -> Function {
+    let cb = ctx.link().callback(WebTermMessage::Event);
+    let func = move |event: JsValue| {
+        let event: ClipboardEvent = event.into();
+        let editable = event
+            .target()
+            .and_then(|t| t.dyn_into::<HtmlElement>().ok())
+            .is_some_and(|elem| {
+                elem.is_content_editable()
+                    || elem.is_instance_of::<HtmlInputElement>()
+                    || elem.is_instance_of::<HtmlTextAreaElement>()
+            });
+        if editable {
+            return;
+        }
+        if let Some(text) = event
+            .clipboard_data()
+            .and_then(|data| data.get_data("text/plain").ok())
+            .filter(|text| !text.is_empty())
+        {
+            cb.emit(TermEvent::Paste(text));
+        }
+    };
+    Closure::<dyn 'static + Fn(JsValue)>::new(func)
+        .into_js_value()
+        .into()
+}
+
+/// Returns the zoom that the given key press is bound to, if any.
+pub(crate) fn zoom_motion(event: &KeyboardEvent) -> Option<ZoomMotion> {
+    if !(event.ctrl_key() || event.meta_key()) {
+        return None;
+    }
+    match event.key().as_str() {
+        "=" | "+" => Some(ZoomMotion::In),
+        "-" => Some(ZoomMotion::Out),
+        "0" => Some(ZoomMotion::Reset),
+        _ => None,
+    }
+}
+
+pub(crate) fn process_zoom_key_event<A: TerminalApp>(ctx: &Context<WebTerminal<A>>) -> Function {
+    let cb = ctx.link().callback(WebTermMessage::Zoom);
+    let func = move |event: JsValue| {
+        let event: KeyboardEvent = event.into();
+        if let Some(motion) = zoom_motion(&event) {
+            event.prevent_default();
+            cb.emit(motion);
+        }
+    };
+    Closure::<dyn 'static + Fn(JsValue)>::new(func)
+        .into_js_value()
+        .into()
+}
+
 // In order to emulate scrolling on mobile, a simple (perhaps too simple) approach is
 // taken. Touch events are started in an accumulator behind a `RefCell`. This accumulator
 // tracks when two touches should be connected and tracks the overall progress. When enough